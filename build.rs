@@ -4,6 +4,10 @@ fn main() {
         schemafy_lib::Generator::builder()
             .with_root_name_str("Schema")
             .with_input_file(schema_path)
+            // Keeps integer bounds exactly, which `f64` does not beyond 2^53
+            .with_number_type(schemafy_lib::NumberType::Number)
+            // Formatted like the rest of the crate, so `cargo fmt` leaves it as it is
+            .with_rustfmt(true)
             .build()
             .generate_to_file("schemafy_lib/src/schema.rs")
             .unwrap();
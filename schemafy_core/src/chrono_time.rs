@@ -0,0 +1,53 @@
+use chrono::{DateTime, FixedOffset, NaiveTime};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A time of day with an offset from UTC, the `time` format of JSON schema, which `chrono` has
+/// no type for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChronoTime {
+    pub time: NaiveTime,
+    pub offset: FixedOffset,
+}
+
+impl Serialize for ChronoTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let time = self.time.format("%H:%M:%S%.f");
+        serializer.collect_str(&format_args!("{}{}", time, self.offset))
+    }
+}
+
+impl<'de> Deserialize<'de> for ChronoTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let time = <&str>::deserialize(deserializer)?;
+        // Parsed as the time of a date-time, which has the same syntax
+        let date_time = DateTime::parse_from_rfc3339(&format!("1970-01-01T{}", time))
+            .map_err(de::Error::custom)?;
+        Ok(ChronoTime {
+            time: date_time.time(),
+            offset: *date_time.offset(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn round_trips() {
+        let json = r#""03:04:05.500+01:00""#;
+        let time: ChronoTime = from_str(json).unwrap();
+        assert_eq!(time.time.hour(), 3);
+        assert_eq!(time.offset.local_minus_utc(), 3600);
+        assert_eq!(to_string(&time).unwrap(), json);
+        from_str::<ChronoTime>(r#""03:04:05""#).unwrap_err();
+    }
+}
@@ -1 +1,62 @@
-pub mod one_or_many;
+#[cfg(feature = "chrono")]
+pub mod chrono_time;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+pub mod never;
+pub mod one_or_many;
+#[cfg(feature = "time")]
+pub mod rfc3339;
+pub mod tuple_rest;
+
+#[cfg(feature = "chrono")]
+pub use chrono_time::ChronoTime;
+#[cfg(feature = "decimal")]
+pub use decimal::Decimal;
+pub use never::Never;
+#[cfg(feature = "time")]
+pub use rfc3339::{Rfc3339, Rfc3339Time};
+pub use tuple_rest::TupleRest;
+
+/// Invoked by generated code using `Decimal`, to report the missing `decimal` feature clearly.
+#[cfg(feature = "decimal")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! require_decimal {
+    () => {};
+}
+
+#[cfg(not(feature = "decimal"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! require_decimal {
+    () => {
+        compile_error!(
+            "the generated code uses `schemafy_core::Decimal`, which needs the `decimal` feature of `schemafy_core`"
+        );
+    };
+}
+
+// Generated code refers to these through `schemafy_core`, so crates using it do not need to
+// depend on them directly
+#[doc(hidden)]
+pub use serde;
+#[doc(hidden)]
+pub use serde_json;
+#[doc(hidden)]
+pub use serde_repr;
+
+#[cfg(feature = "chrono")]
+#[doc(hidden)]
+pub use chrono;
+#[cfg(feature = "email")]
+#[doc(hidden)]
+pub use email_address;
+#[cfg(feature = "time")]
+#[doc(hidden)]
+pub use time;
+#[cfg(feature = "url")]
+#[doc(hidden)]
+pub use url;
+#[cfg(feature = "uuid")]
+#[doc(hidden)]
+pub use uuid;
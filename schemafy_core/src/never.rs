@@ -0,0 +1,42 @@
+use serde::de::{self, IgnoredAny};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The type of the `false` schema, which no JSON value is valid against.
+///
+/// `Never` has no values so deserializing it always fails, a property declared as `false` is
+/// generated as an `Option<Never>` which can only be `None`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Never {}
+
+impl Serialize for Never {
+    fn serialize<S>(&self, _: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {}
+    }
+}
+
+impl<'de> Deserialize<'de> for Never {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IgnoredAny::deserialize(deserializer)?;
+        Err(de::Error::custom(
+            "no value is allowed by the `false` schema",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_everything() {
+        assert!(serde_json::from_str::<Never>("null").is_err());
+        assert!(serde_json::from_str::<Never>("{}").is_err());
+        assert_eq!(serde_json::from_str::<Option<Never>>("null").unwrap(), None);
+    }
+}
@@ -0,0 +1,114 @@
+use std::ops::Deref;
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use time::{format_description::well_known, OffsetDateTime, Time, UtcOffset};
+
+/// An `OffsetDateTime` which is (de)serialized as an RFC 3339 string, the `date-time` format of
+/// JSON schema.
+///
+/// The `serde` implementations of `OffsetDateTime` itself use a different format.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Rfc3339(pub OffsetDateTime);
+
+impl Deref for Rfc3339 {
+    type Target = OffsetDateTime;
+
+    fn deref(&self) -> &OffsetDateTime {
+        &self.0
+    }
+}
+
+impl From<OffsetDateTime> for Rfc3339 {
+    fn from(date_time: OffsetDateTime) -> Self {
+        Rfc3339(date_time)
+    }
+}
+
+impl From<Rfc3339> for OffsetDateTime {
+    fn from(date_time: Rfc3339) -> Self {
+        date_time.0
+    }
+}
+
+impl Serialize for Rfc3339 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        time::serde::rfc3339::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rfc3339 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        time::serde::rfc3339::deserialize(deserializer).map(Rfc3339)
+    }
+}
+
+/// A time of day with an offset from UTC, (de)serialized as an RFC 3339 string, the `time`
+/// format of JSON schema.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rfc3339Time {
+    pub time: Time,
+    pub offset: UtcOffset,
+}
+
+impl Serialize for Rfc3339Time {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Formatted as the time of a date-time, which has the same syntax
+        let date_time = OffsetDateTime::UNIX_EPOCH
+            .date()
+            .with_time(self.time)
+            .assume_offset(self.offset)
+            .format(&well_known::Rfc3339)
+            .map_err(ser::Error::custom)?;
+        let (_date, time) = date_time.split_once('T').unwrap_or_default();
+        serializer.serialize_str(time)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rfc3339Time {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let time = <&str>::deserialize(deserializer)?;
+        let date_time =
+            OffsetDateTime::parse(&format!("1970-01-01T{}", time), &well_known::Rfc3339)
+                .map_err(de::Error::custom)?;
+        Ok(Rfc3339Time {
+            time: date_time.time(),
+            offset: date_time.offset(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn round_trips() {
+        let json = r#""2020-01-02T03:04:05.5+01:00""#;
+        let date_time: Rfc3339 = from_str(json).unwrap();
+        assert_eq!(date_time.hour(), 3);
+        assert_eq!(to_string(&date_time).unwrap(), json);
+    }
+
+    #[test]
+    fn times_round_trip() {
+        let json = r#""03:04:05.5+01:00""#;
+        let time: Rfc3339Time = from_str(json).unwrap();
+        assert_eq!(time.time.hour(), 3);
+        assert_eq!(time.offset.whole_hours(), 1);
+        assert_eq!(to_string(&time).unwrap(), json);
+        from_str::<Rfc3339Time>(r#""03:04:05""#).unwrap_err();
+    }
+}
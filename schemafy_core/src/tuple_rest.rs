@@ -0,0 +1,150 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// A JSON array which starts with the elements of the tuple `T` followed by any number of `R`
+/// elements.
+///
+/// Used for arrays declared with `prefixItems` and `items`, so `[1, "a", true, false]` can be
+/// represented as `TupleRest((1, "a"), vec![true, false])`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TupleRest<T, R>(pub T, pub Vec<R>);
+
+/// Tuples which can be (de)serialized as the first elements of a JSON array.
+pub trait TuplePrefix: Sized {
+    /// The number of elements in the tuple.
+    const LEN: usize;
+
+    fn serialize_prefix<S>(&self, seq: &mut S) -> Result<(), S::Error>
+    where
+        S: SerializeSeq;
+
+    fn deserialize_prefix<'de, A>(seq: &mut A) -> Result<Self, A::Error>
+    where
+        A: SeqAccess<'de>;
+}
+
+macro_rules! impl_tuple_prefix {
+    ($len: expr => $($name: ident $index: tt)+) => {
+        impl<$($name),+> TuplePrefix for ($($name,)+)
+        where
+            $($name: Serialize + DeserializeOwned),+
+        {
+            const LEN: usize = $len;
+
+            fn serialize_prefix<S>(&self, seq: &mut S) -> Result<(), S::Error>
+            where
+                S: SerializeSeq,
+            {
+                $(seq.serialize_element(&self.$index)?;)+
+                Ok(())
+            }
+
+            fn deserialize_prefix<'de, A>(seq: &mut A) -> Result<Self, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                Ok(($(
+                    seq.next_element::<$name>()?
+                        .ok_or_else(|| de::Error::invalid_length($index, &stringify!($len)))?,
+                )+))
+            }
+        }
+    };
+}
+
+impl_tuple_prefix!(1 => T0 0);
+impl_tuple_prefix!(2 => T0 0 T1 1);
+impl_tuple_prefix!(3 => T0 0 T1 1 T2 2);
+impl_tuple_prefix!(4 => T0 0 T1 1 T2 2 T3 3);
+impl_tuple_prefix!(5 => T0 0 T1 1 T2 2 T3 3 T4 4);
+impl_tuple_prefix!(6 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5);
+impl_tuple_prefix!(7 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6);
+impl_tuple_prefix!(8 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7);
+impl_tuple_prefix!(9 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8);
+impl_tuple_prefix!(10 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9);
+impl_tuple_prefix!(11 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10);
+impl_tuple_prefix!(12 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11);
+
+impl<T, R> Serialize for TupleRest<T, R>
+where
+    T: TuplePrefix,
+    R: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(T::LEN + self.1.len()))?;
+        self.0.serialize_prefix(&mut seq)?;
+        for element in &self.1 {
+            seq.serialize_element(element)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T, R> Deserialize<'de> for TupleRest<T, R>
+where
+    T: TuplePrefix,
+    R: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TupleRestVisitor<T, R>(PhantomData<(T, R)>);
+        impl<'de, T, R> Visitor<'de> for TupleRestVisitor<T, R>
+        where
+            T: TuplePrefix,
+            R: Deserialize<'de>,
+        {
+            type Value = TupleRest<T, R>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(formatter, "an array of at least {} elements", T::LEN)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let prefix = T::deserialize_prefix(&mut seq)?;
+                let mut rest = Vec::new();
+                while let Some(element) = seq.next_element()? {
+                    rest.push(element);
+                }
+                Ok(TupleRest(prefix, rest))
+            }
+        }
+        deserializer.deserialize_seq(TupleRestVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn round_trip() {
+        let value: TupleRest<(i32, String), bool> = from_str(r#"[1, "a", true, false]"#).unwrap();
+        assert_eq!(value, TupleRest((1, "a".to_string()), vec![true, false]));
+        assert_eq!(to_string(&value).unwrap(), r#"[1,"a",true,false]"#);
+    }
+
+    #[test]
+    fn empty_rest() {
+        let value: TupleRest<(i32,), bool> = from_str("[1]").unwrap();
+        assert_eq!(value, TupleRest((1,), vec![]));
+    }
+
+    #[test]
+    fn missing_prefix() {
+        assert!(from_str::<TupleRest<(i32, String), bool>>("[1]").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! Generating types from build scripts.
+//!
+//! Instead of expanding the `schemafy!` macro on every compile, a build
+//! script can write a formatted module for each schema to `OUT_DIR`:
+//!
+//! ```no_run
+//! // `main` of build.rs
+//! schemafy_lib::build::Build::new()
+//!     .with_schema("schemas/*.json")
+//!     .generate()
+//!     .unwrap();
+//! ```
+//!
+//! ```ignore
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/person.rs"));
+//! ```
+//!
+//! Each module is named after its schema file, and so is its root type
+//! (`person.json` defines `Person`). Two schema files with the same name
+//! are an error, as their types would clash.
+//!
+//! The modules are meant to be included side by side, so each type is only
+//! generated once: a schema referring to another schema of the build uses
+//! the types of its module, and a document referenced by several schemas
+//! (but not matched itself) gets a module of its own, which is returned
+//! with the others. A module file is only written when its contents change,
+//! so regenerating it does not make cargo rebuild the crate needlessly.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use inflector::Inflector;
+
+use crate::{documents::Documents, generator::get_crate_root, Error, Generator, GeneratorBuilder};
+
+type Configure = Box<dyn for<'a, 'b> Fn(GeneratorBuilder<'a, 'b>) -> GeneratorBuilder<'a, 'b>>;
+
+/// Generates a module for each of a set of schemas, see the
+/// [module documentation](self).
+#[derive(Default)]
+#[must_use]
+pub struct Build {
+    schemas: Vec<String>,
+    out_dir: Option<PathBuf>,
+    configure: Option<Configure>,
+}
+
+impl Build {
+    pub fn new() -> Build {
+        Build::default()
+    }
+
+    /// Generate a module for the schema file `pattern`, relative to the
+    /// crate root, or for every file matching it if it is a glob such as
+    /// `schemas/**/*.json`.
+    pub fn with_schema(mut self, pattern: impl Into<String>) -> Self {
+        self.schemas.push(pattern.into());
+        self
+    }
+
+    /// Write the modules to `out_dir` instead of `OUT_DIR`.
+    pub fn with_out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Configure the generator of every schema, after its root name and
+    /// input file are set.
+    pub fn with_generator<F>(mut self, configure: F) -> Self
+    where
+        F: for<'a, 'b> Fn(GeneratorBuilder<'a, 'b>) -> GeneratorBuilder<'a, 'b> + 'static,
+    {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Writes the modules, returning their paths.
+    ///
+    /// `cargo:rerun-if-changed` is printed for every file read, and for
+    /// the directories searched by globs so new schemas are picked up.
+    pub fn generate(&self) -> Result<Vec<PathBuf>, Error> {
+        let out_dir = match self.out_dir {
+            Some(ref out_dir) => out_dir.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| Error::Io {
+                    file: PathBuf::from("OUT_DIR"),
+                    source: io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "`OUT_DIR` is not set, run from a build script or use `with_out_dir`",
+                    ),
+                })?,
+        };
+        std::fs::create_dir_all(&out_dir).map_err(|err| Error::Io {
+            file: out_dir.clone(),
+            source: err,
+        })?;
+
+        let mut files = Vec::new();
+        for pattern in &self.schemas {
+            for file in self.schema_files(pattern)? {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        let mut modules = files
+            .into_iter()
+            .map(|file| self.load_module(file))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.add_shared_modules(&mut modules)?;
+        check_module_names(&modules)?;
+
+        let module_files = modules
+            .iter()
+            .map(|module| (canonical(&module.file), root_name(&module.file)))
+            .collect::<BTreeMap<_, _>>();
+        modules
+            .into_iter()
+            .map(|module| self.generate_module(module, &module_files, &out_dir))
+            .collect()
+    }
+
+    /// Adds a module for every document which is read by more than one
+    /// module, until there are none left.
+    fn add_shared_modules(&self, modules: &mut Vec<Module>) -> Result<(), Error> {
+        loop {
+            let mut readers = BTreeMap::<PathBuf, usize>::new();
+            for module in modules.iter() {
+                for document in module.documents.external.values() {
+                    *readers.entry(canonical(&document.file)).or_default() += 1;
+                }
+            }
+            for module in modules.iter() {
+                readers.remove(&canonical(&module.file));
+            }
+            // Documents which are not files (bundled ones) can not be
+            // generated on their own
+            let shared = readers
+                .into_iter()
+                .filter(|(file, readers)| *readers > 1 && file.is_file())
+                .map(|(file, _)| file)
+                .collect::<Vec<_>>();
+            if shared.is_empty() {
+                return Ok(());
+            }
+            for file in shared {
+                modules.push(self.load_module(file)?);
+            }
+        }
+    }
+
+    fn generator<'a, 'b>(&self, file: &'b Path) -> Generator<'a, 'b> {
+        let builder = Generator::builder()
+            .with_root_name(Some(root_name(file)))
+            .with_input_file(file);
+        match self.configure {
+            Some(ref configure) => configure(builder),
+            None => builder,
+        }
+        .build()
+    }
+
+    fn load_module(&self, file: PathBuf) -> Result<Module, Error> {
+        let documents = self.generator(&file).load()?;
+        Ok(Module { file, documents })
+    }
+
+    /// The files `pattern` refers to.
+    fn schema_files(&self, pattern: &str) -> Result<Vec<PathBuf>, Error> {
+        let crate_root = get_crate_root().map_err(|err| Error::Io {
+            file: PathBuf::from(pattern),
+            source: err,
+        })?;
+        let path = crate_root.join(pattern);
+        if !pattern.contains(['*', '?', '[']) {
+            return Ok(vec![path]);
+        }
+
+        // Rerun when a file is added to the directories the glob searches
+        let directory = path
+            .components()
+            .take_while(|component| match component {
+                Component::Normal(name) => !name.to_string_lossy().contains(['*', '?', '[']),
+                _ => true,
+            })
+            .collect::<PathBuf>();
+        println!("cargo:rerun-if-changed={}", directory.display());
+
+        let invalid = |message: String| Error::Io {
+            file: PathBuf::from(pattern),
+            source: io::Error::new(io::ErrorKind::InvalidInput, message),
+        };
+        let entries = glob::glob(&path.to_string_lossy())
+            .map_err(|err| invalid(format!("invalid glob: {}", err)))?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let file = entry.map_err(|err| Error::Io {
+                file: err.path().to_owned(),
+                source: err.into(),
+            })?;
+            files.push(file);
+        }
+        if files.is_empty() {
+            return Err(invalid("no schema matches the glob".into()));
+        }
+        Ok(files)
+    }
+
+    fn generate_module(
+        &self,
+        mut module: Module,
+        module_files: &BTreeMap<PathBuf, String>,
+        out_dir: &Path,
+    ) -> Result<PathBuf, Error> {
+        // The types of the documents with a module of their own are used from there
+        for document in module.documents.external.values_mut() {
+            if let Some(root_name) = module_files.get(&canonical(&document.file)) {
+                document.name.clone_from(root_name);
+                document.own_module = true;
+            }
+        }
+
+        let documents = module.documents;
+        let path = out_dir.join(format!("{}.rs", module_name(&module.file)));
+        let generator = self.generator(&module.file);
+        let tokens = generator.expand(&documents)?;
+        let code = generator.format(&tokens)?;
+        rerun_if_changed(&documents.files);
+
+        // Rewriting an unchanged module would still make cargo rebuild the crate including it
+        if matches!(std::fs::read_to_string(&path), Ok(existing) if existing == code) {
+            return Ok(path);
+        }
+        std::fs::write(&path, &code).map_err(|err| Error::Io {
+            file: path.clone(),
+            source: err,
+        })?;
+        Ok(path)
+    }
+}
+
+/// A schema file which a module is generated for.
+struct Module {
+    file: PathBuf,
+    /// The schema and the documents it references.
+    documents: Documents,
+}
+
+/// The name of the module generated for the schema `file`.
+fn module_name(file: &Path) -> String {
+    file.file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().to_snake_case())
+}
+
+/// The name of the root type of the module generated for `file`.
+fn root_name(file: &Path) -> String {
+    module_name(file).to_pascal_case()
+}
+
+/// Fails if two modules would be written to the same file.
+fn check_module_names(modules: &[Module]) -> Result<(), Error> {
+    let mut names = BTreeMap::new();
+    for module in modules {
+        let name = module_name(&module.file);
+        if let Some(other) = names.insert(name.clone(), &module.file) {
+            return Err(Error::Io {
+                file: module.file.clone(),
+                source: io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "the module `{}` is already generated for `{}`",
+                        name,
+                        other.display()
+                    ),
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// `file` with symbolic links and `..` resolved, so the same file is
+/// always the same path.
+fn canonical(file: &Path) -> PathBuf {
+    std::fs::canonicalize(file).unwrap_or_else(|_| file.to_owned())
+}
+
+fn rerun_if_changed(files: &[PathBuf]) {
+    for file in files {
+        println!("cargo:rerun-if-changed={}", file.display());
+    }
+}
@@ -0,0 +1,483 @@
+//! Derives `Eq`, `Hash`, `PartialOrd` and `Ord` for the generated types whose fields all
+//! implement them.
+
+use std::collections::BTreeMap;
+
+use proc_macro2::TokenStream;
+use syn::{punctuated::Punctuated, Token};
+
+const EQ: u8 = 1;
+const HASH: u8 = 2;
+const PARTIAL_ORD: u8 = 4;
+const ORD: u8 = 8;
+const ALL: u8 = EQ | HASH | PARTIAL_ORD | ORD;
+
+const TRAITS: [(u8, &str); 4] = [
+    (EQ, "Eq"),
+    (HASH, "Hash"),
+    (PARTIAL_ORD, "PartialOrd"),
+    (ORD, "Ord"),
+];
+
+/// The paths of the types outside of the generated code the comparison traits are known for.
+pub(crate) struct Paths<'a> {
+    /// The path to `schemafy_core`, ending with `::`.
+    pub schemafy_path: &'a str,
+    /// The map type used for objects without fixed properties.
+    pub map_type: &'a str,
+}
+
+/// Adds the comparison traits to the derives of the types in `tokens` wherever every field
+/// implements them, unless `removed(type_name, trait_name)`.
+///
+/// Types are assumed to implement every trait they may derive until one of their fields is
+/// found not to, so types which refer to each other through a cycle still derive them.
+pub(crate) fn derive_comparisons(
+    tokens: TokenStream,
+    paths: &Paths<'_>,
+    removed: impl Fn(&str, &str) -> bool,
+) -> syn::Result<TokenStream> {
+    let mut file = syn::parse2::<syn::File>(tokens)?;
+
+    let mut items = Items {
+        types: BTreeMap::new(),
+        uses: BTreeMap::new(),
+        external: external_types(paths),
+    };
+    collect_types(&file.items, &mut Vec::new(), &removed, &mut items);
+
+    // Remove the traits of types with a field which does not implement them, until none change
+    loop {
+        let mut changed = false;
+        let keys = items.types.keys().cloned().collect::<Vec<_>>();
+        for key in keys {
+            let ty = &items.types[&key];
+            let mut traits = ty.candidates;
+            for field in &ty.fields {
+                traits &= field_traits(field, &key[..key.len() - 1], &items);
+            }
+            traits = sound(traits | ty.derives, ty.partial_eq);
+            if traits != ty.traits {
+                items.types.get_mut(&key).unwrap().traits = traits;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    add_derives(&mut file.items, &mut Vec::new(), &items.types);
+    Ok(quote! { #file })
+}
+
+/// The types of the generated code, keyed by the path of their module followed by their name.
+struct Items {
+    types: BTreeMap<Vec<String>, Type>,
+    /// The paths of the types imported by `use`, keyed like the types.
+    uses: BTreeMap<Vec<String>, Vec<String>>,
+    /// The comparison traits of types outside of the generated code, keyed by `path_key`.
+    external: BTreeMap<String, External>,
+}
+
+struct Type {
+    /// The types of the fields, or the aliased type.
+    fields: Vec<syn::Type>,
+    /// The comparison traits already derived.
+    derives: u8,
+    /// The comparison traits which may be derived.
+    candidates: u8,
+    /// Whether `PartialEq` is derived, which all of the traits but `Hash` require.
+    partial_eq: bool,
+    /// The comparison traits implemented, as far as known.
+    traits: u8,
+}
+
+#[derive(Clone, Copy)]
+enum External {
+    /// Implements these traits whatever its type arguments.
+    Traits(u8),
+    /// Implements these traits where all of its type arguments do.
+    Arguments(u8),
+}
+
+fn external_types(paths: &Paths<'_>) -> BTreeMap<String, External> {
+    let schemafy = |name: &str| format!("{}{}", paths.schemafy_path, name);
+    let mut types = vec![
+        (schemafy("Never"), External::Traits(ALL)),
+        (schemafy("TupleRest"), External::Arguments(ALL)),
+        (schemafy("Decimal"), External::Traits(ALL)),
+        (schemafy("serde_json::Number"), External::Traits(EQ | HASH)),
+        // The types of string formats
+        (schemafy("chrono::DateTime"), External::Traits(ALL)),
+        (schemafy("chrono::NaiveDate"), External::Traits(ALL)),
+        (schemafy("time::Date"), External::Traits(ALL)),
+        (schemafy("Rfc3339"), External::Traits(ALL)),
+        (schemafy("uuid::Uuid"), External::Traits(ALL)),
+        (schemafy("url::Url"), External::Traits(ALL)),
+        ("::std::net::Ipv4Addr".to_owned(), External::Traits(ALL)),
+        ("::std::net::Ipv6Addr".to_owned(), External::Traits(ALL)),
+        (
+            schemafy("email_address::EmailAddress"),
+            External::Traits(EQ | HASH),
+        ),
+        (schemafy("ChronoTime"), External::Traits(EQ | HASH)),
+        (schemafy("Rfc3339Time"), External::Traits(EQ | HASH)),
+    ];
+    // Only the map types known to implement the traits, any other may not
+    let map_traits = match paths.map_type.trim_start_matches("::") {
+        "std::collections::BTreeMap" => Some(ALL),
+        "std::collections::HashMap" | "indexmap::IndexMap" => Some(EQ),
+        _ => None,
+    };
+    if let Some(traits) = map_traits {
+        types.push((paths.map_type.to_owned(), External::Arguments(traits)));
+    }
+    types
+        .into_iter()
+        .filter_map(|(path, traits)| {
+            let path = syn::parse_str::<syn::Path>(&path).ok()?;
+            Some((path_key(&path), traits))
+        })
+        .collect()
+}
+
+/// The path `path` without its type arguments, to compare paths by.
+fn path_key(path: &syn::Path) -> String {
+    let mut path = path.clone();
+    for segment in &mut path.segments {
+        segment.arguments = syn::PathArguments::None;
+    }
+    quote!(#path).to_string()
+}
+
+fn collect_types(
+    items: &[syn::Item],
+    module: &mut Vec<String>,
+    removed: &impl Fn(&str, &str) -> bool,
+    collected: &mut Items,
+) {
+    for item in items {
+        let (ident, attrs, fields) = match item {
+            syn::Item::Struct(item) => (
+                &item.ident,
+                &item.attrs[..],
+                item.fields.iter().map(|field| field.ty.clone()).collect(),
+            ),
+            syn::Item::Enum(item) => (
+                &item.ident,
+                &item.attrs[..],
+                item.variants
+                    .iter()
+                    .flat_map(|variant| variant.fields.iter().map(|field| field.ty.clone()))
+                    .collect(),
+            ),
+            syn::Item::Type(item) => {
+                collected.types.insert(
+                    item_key(module, &item.ident),
+                    Type {
+                        fields: vec![(*item.ty).clone()],
+                        derives: 0,
+                        candidates: ALL,
+                        partial_eq: true,
+                        traits: ALL,
+                    },
+                );
+                continue;
+            }
+            // Only relative paths may refer to the generated code
+            syn::Item::Use(item) if item.leading_colon.is_none() => {
+                collect_uses(&item.tree, module, &mut Vec::new(), collected);
+                continue;
+            }
+            syn::Item::Mod(item) => {
+                if let Some((_, ref items)) = item.content {
+                    module.push(item.ident.to_string());
+                    collect_types(items, module, removed, collected);
+                    module.pop();
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let name = ident.to_string();
+        let derives = derives(attrs);
+        let has = |derive: &str| derives.iter().any(|path| is_trait(path, derive));
+        let mut candidates = 0;
+        let mut existing = 0;
+        for &(flag, trait_name) in &TRAITS {
+            if has(trait_name) {
+                existing |= flag;
+            } else if !removed(&name, trait_name) {
+                candidates |= flag;
+            }
+        }
+        collected.types.insert(
+            item_key(module, ident),
+            Type {
+                fields,
+                derives: existing,
+                candidates,
+                partial_eq: has("PartialEq"),
+                traits: ALL,
+            },
+        );
+    }
+}
+
+/// The key of the type `ident` declared in `module`.
+fn item_key(module: &[String], ident: &syn::Ident) -> Vec<String> {
+    let mut key = module.to_vec();
+    key.push(ident.to_string());
+    key
+}
+
+/// Records the names `tree` imports, where it is used in `module` after the path `prefix`.
+fn collect_uses(
+    tree: &syn::UseTree,
+    module: &[String],
+    prefix: &mut Vec<String>,
+    collected: &mut Items,
+) {
+    let mut import = |ident: &syn::Ident, rename: &syn::Ident| {
+        let mut path = prefix.clone();
+        path.push(ident.to_string());
+        let mut key = module.to_vec();
+        key.push(rename.to_string());
+        collected.uses.insert(key, path);
+    };
+    match tree {
+        syn::UseTree::Path(tree) => {
+            prefix.push(tree.ident.to_string());
+            collect_uses(&tree.tree, module, prefix, collected);
+            prefix.pop();
+        }
+        syn::UseTree::Name(tree) => import(&tree.ident, &tree.ident),
+        syn::UseTree::Rename(tree) => import(&tree.ident, &tree.rename),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_uses(tree, module, prefix, collected);
+            }
+        }
+        syn::UseTree::Glob(_) => {}
+    }
+}
+
+/// The type of the generated code `path`, as written in `module`, refers to, if any.
+fn local_type<'a>(path: &syn::Path, module: &[String], items: &'a Items) -> Option<&'a Type> {
+    if path.leading_colon.is_some() {
+        return None;
+    }
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string());
+    let mut key = module_path(module, segments)?;
+    // Follow the imports, of which there are few in the generated code
+    for _ in 0..8 {
+        if let Some(ty) = items.types.get(&key) {
+            return Some(ty);
+        }
+        let (used, path) = items.uses.iter().find(|(used, _)| key.starts_with(used))?;
+        let rest = key[used.len()..].iter().cloned();
+        key = module_path(&used[..used.len() - 1], path.iter().cloned().chain(rest))?;
+    }
+    None
+}
+
+/// The path from the root of the generated code of the relative path `segments` in `module`.
+fn module_path(module: &[String], segments: impl Iterator<Item = String>) -> Option<Vec<String>> {
+    let mut path = module.to_vec();
+    let mut segments = segments.peekable();
+    while let Some(segment) = segments.next_if(|segment| segment == "self" || segment == "super") {
+        if segment == "super" {
+            path.pop()?;
+        }
+    }
+    path.extend(segments);
+    Some(path)
+}
+
+/// Restricts `traits` to the ones whose supertraits are implemented.
+fn sound(mut traits: u8, partial_eq: bool) -> u8 {
+    if !partial_eq {
+        traits &= HASH;
+    }
+    if traits & (EQ | PARTIAL_ORD) != EQ | PARTIAL_ORD {
+        traits &= !ORD;
+    }
+    traits
+}
+
+/// The comparison traits implemented by the field type `ty` of a type in `module`.
+fn field_traits(ty: &syn::Type, module: &[String], items: &Items) -> u8 {
+    let path = match ty {
+        syn::Type::Path(ty) if ty.qself.is_none() => &ty.path,
+        syn::Type::Tuple(tuple) => {
+            return tuple.elems.iter().fold(ALL, |traits, elem| {
+                traits & field_traits(elem, module, items)
+            })
+        }
+        _ => return 0,
+    };
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return 0,
+    };
+    let arguments = || match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref arguments) => {
+            arguments
+                .args
+                .iter()
+                .fold(ALL, |traits, argument| match argument {
+                    syn::GenericArgument::Type(ty) => traits & field_traits(ty, module, items),
+                    _ => traits,
+                })
+        }
+        _ => ALL,
+    };
+    if let Some(ty) = local_type(path, module, items) {
+        return ty.traits;
+    }
+    match items.external.get(&path_key(path)) {
+        Some(External::Traits(traits)) => return *traits,
+        Some(External::Arguments(traits)) => return arguments() & traits,
+        None => {}
+    }
+    // The types of the prelude, which the generated code does not shadow
+    if path.segments.len() > 1 || path.leading_colon.is_some() {
+        return 0;
+    }
+    match &segment.ident.to_string()[..] {
+        "String" | "bool" | "char" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8"
+        | "u16" | "u32" | "u64" | "u128" | "usize" => ALL,
+        "f32" | "f64" => PARTIAL_ORD,
+        "Option" | "Vec" | "Box" => arguments(),
+        _ => 0,
+    }
+}
+
+fn add_derives(
+    items: &mut [syn::Item],
+    module: &mut Vec<String>,
+    types: &BTreeMap<Vec<String>, Type>,
+) {
+    for item in items {
+        let (ident, attrs) = match item {
+            syn::Item::Struct(item) => (&item.ident, &mut item.attrs),
+            syn::Item::Enum(item) => (&item.ident, &mut item.attrs),
+            syn::Item::Mod(item) => {
+                if let Some((_, ref mut items)) = item.content {
+                    module.push(item.ident.to_string());
+                    add_derives(items, module, types);
+                    module.pop();
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let ty = &types[&item_key(module, ident)];
+        let added = ty.traits & !ty.derives;
+        if added == 0 {
+            continue;
+        }
+        let attr = match attrs.iter_mut().find(|attr| attr.path.is_ident("derive")) {
+            Some(attr) => attr,
+            None => continue,
+        };
+        let mut derives = derives(std::slice::from_ref(attr));
+        for &(flag, trait_name) in &TRAITS {
+            if added & flag != 0 {
+                derives.push(syn::Ident::new(trait_name, proc_macro2::Span::call_site()).into());
+            }
+        }
+        *attr = syn::parse_quote!(#[derive(#derives)]);
+    }
+}
+
+/// The paths in the `#[derive]` attributes among `attrs`.
+fn derives(attrs: &[syn::Attribute]) -> Punctuated<syn::Path, Token![,]> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .collect()
+}
+
+fn is_trait(path: &syn::Path, name: &str) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derives_of(tokens: TokenStream) -> BTreeMap<String, Vec<String>> {
+        let paths = Paths {
+            schemafy_path: "::schemafy_core::",
+            map_type: "::std::collections::BTreeMap",
+        };
+        let tokens = derive_comparisons(tokens, &paths, |_, _| false).unwrap();
+        let file = syn::parse2::<syn::File>(tokens).unwrap();
+        let mut derives_of = BTreeMap::new();
+        let mut items = file.items;
+        while let Some(item) = items.pop() {
+            match item {
+                syn::Item::Struct(item) => {
+                    let names = derives(&item.attrs)
+                        .iter()
+                        .map(|path| quote!(#path).to_string())
+                        .collect();
+                    derives_of.insert(item.ident.to_string(), names);
+                }
+                syn::Item::Mod(item) => items.extend(item.content.unwrap().1),
+                _ => {}
+            }
+        }
+        derives_of
+    }
+
+    #[test]
+    fn module_paths() {
+        let derives_of = derives_of(quote! {
+            mod nested {
+                #[derive(PartialEq)]
+                pub struct Float { x: f64 }
+            }
+            use nested::Float as Renamed;
+            #[derive(PartialEq)]
+            pub struct Float { x: i64 }
+            #[derive(PartialEq)]
+            pub struct Local { x: Float }
+            #[derive(PartialEq)]
+            pub struct Nested { x: nested::Float }
+            #[derive(PartialEq)]
+            pub struct Imported { x: Renamed }
+            #[derive(PartialEq)]
+            pub struct Map { x: ::std::collections::BTreeMap<String, Float> }
+            #[derive(PartialEq)]
+            pub struct OtherMap { x: ::my_crate::BTreeMap<String, Float> }
+        });
+        let all = ["PartialEq", "Eq", "Hash", "PartialOrd", "Ord"];
+        assert_eq!(derives_of["Local"], all);
+        assert_eq!(derives_of["Map"], all);
+        assert_eq!(derives_of["Nested"], ["PartialEq", "PartialOrd"]);
+        assert_eq!(derives_of["Imported"], ["PartialEq", "PartialOrd"]);
+        assert_eq!(derives_of["OtherMap"], ["PartialEq"]);
+    }
+
+    #[test]
+    fn invalid_file() {
+        let paths = Paths {
+            schemafy_path: "::schemafy_core::",
+            map_type: "::std::collections::BTreeMap",
+        };
+        assert!(derive_comparisons(quote!(struct;), &paths, |_, _| false).is_err());
+    }
+}
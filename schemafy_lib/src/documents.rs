@@ -0,0 +1,364 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use uriparse::{Fragment, URIReference, URI};
+
+use crate::{
+    draft::walk_subschemas_mut,
+    registry::{Registry, Source},
+    Draft, Error, Schema,
+};
+
+/// A schema document referenced from the root schema.
+pub(crate) struct Document {
+    /// The name of the type generated for the document itself.
+    pub(crate) name: String,
+    /// The file the document was read from, or its URI if it is bundled.
+    pub(crate) file: PathBuf,
+    pub(crate) schema: Schema,
+    /// The document as read, for the JSON pointers which do not point to a subschema.
+    pub(crate) value: Value,
+    /// Whether the document itself is referenced, and not only its definitions.
+    pub(crate) referenced: bool,
+    /// Whether the types of the document are generated in a module of their own by
+    /// [`Build`](crate::build::Build), rather than together with the root schema.
+    pub(crate) own_module: bool,
+}
+
+/// A root schema together with every document it references, directly or through other
+/// documents.
+///
+/// The `$ref`s of all documents are rewritten while loading, references into the root document
+/// become plain fragments (`#/definitions/A`) and references into any other document become
+/// absolute URIs (`https://example.com/common.json#/definitions/A`) which are the keys of
+/// `external`.
+pub(crate) struct Documents {
+    pub(crate) root: Schema,
+    /// The file the root schema was read from, `None` for a schema passed in memory.
+    pub(crate) root_file: Option<PathBuf>,
+    pub(crate) root_value: Value,
+    /// The draft of the root schema.
+    pub(crate) draft: Draft,
+    pub(crate) external: BTreeMap<String, Document>,
+    /// Every file which was read, the root file first.
+    pub(crate) files: Vec<PathBuf>,
+}
+
+impl Documents {
+    /// Loads the schema in `file` and the documents it references.
+    ///
+    /// Referenced documents are looked up relative to the file referring to them, using their
+    /// base URI (the `$id` of the document) to resolve references between absolute URIs. Any
+    /// other absolute URI is looked up in the `registry`.
+    pub(crate) fn load(
+        file: &Path,
+        draft: Option<Draft>,
+        registry: &Registry,
+    ) -> Result<Documents, Error> {
+        let source = Source::File(file.to_owned());
+        let document = read_json(&source)?;
+        Documents::new(document, source, draft, registry)
+    }
+
+    /// Loads the schema `document` and the documents it references, resolving relative
+    /// references against `directory`.
+    pub(crate) fn from_value(
+        document: Value,
+        directory: &Path,
+        draft: Option<Draft>,
+        registry: &Registry,
+    ) -> Result<Documents, Error> {
+        Documents::new(
+            document,
+            Source::Inline(directory.to_owned()),
+            draft,
+            registry,
+        )
+    }
+
+    fn new(
+        mut document: Value,
+        source: Source,
+        draft: Option<Draft>,
+        registry: &Registry,
+    ) -> Result<Documents, Error> {
+        let draft = match draft {
+            Some(draft) => draft,
+            None => detect_draft(&document, &source)?.unwrap_or_default(),
+        };
+
+        let (file_uri, root_file) = match source {
+            Source::File(ref file) => (file_uri(file), Some(file.clone())),
+            // Relative references resolve as if the schema were a file in the directory
+            Source::Inline(ref directory) => (format!("{}/", file_uri(directory)), None),
+            Source::Bundled(ref uri, _) => (uri.clone(), None),
+        };
+        let base = base_uri(&document, draft, &file_uri);
+        let root_uri = document_uri(&base);
+        let mut loader = Loader {
+            registry,
+            root: root_uri.clone(),
+            aliases: BTreeMap::new(),
+            queue: Vec::new(),
+            referenced: BTreeSet::new(),
+        };
+        loader.alias(&file_uri, &root_uri);
+        loader.alias(&base, &root_uri);
+        let root = loader.parse(&root_uri, &base, &source, &mut document, draft)?;
+        let mut files = root_file.iter().cloned().collect::<Vec<_>>();
+
+        let mut external = BTreeMap::new();
+        while let Some((key, source)) = loader.queue.pop() {
+            let mut document = read_json(&source)?;
+            if let Source::File(ref file) = source {
+                files.push(file.clone());
+            }
+            let document_draft = detect_draft(&document, &source)?.unwrap_or(draft);
+            let base = base_uri(&document, document_draft, &key);
+            loader.alias(&base, &key);
+            let schema = loader.parse(&key, &base, &source, &mut document, document_draft)?;
+            let name = key
+                .rsplit('/')
+                .next()
+                .and_then(|name| name.split('.').next())
+                .unwrap_or("")
+                .to_owned();
+            external.insert(
+                key,
+                Document {
+                    name,
+                    file: source.file(),
+                    schema,
+                    value: document,
+                    referenced: false,
+                    own_module: false,
+                },
+            );
+        }
+        for key in &loader.referenced {
+            if let Some(document) = external.get_mut(key) {
+                document.referenced = true;
+            }
+        }
+
+        Ok(Documents {
+            root,
+            root_file,
+            root_value: document,
+            draft,
+            external,
+            files,
+        })
+    }
+}
+
+struct Loader<'a> {
+    registry: &'a Registry,
+    /// The URI of the root document.
+    root: String,
+    /// Maps every URI a document is known by to the URI it is stored under.
+    aliases: BTreeMap<String, String>,
+    /// Documents which have been found but not yet loaded.
+    queue: Vec<(String, Source)>,
+    /// Documents which are referenced as a whole.
+    referenced: BTreeSet<String>,
+}
+
+impl Loader<'_> {
+    /// Finds the document `target` which `reference` refers to from `source`, whose base URI
+    /// is `base`.
+    fn locate(
+        &self,
+        reference: &URIReference,
+        base: &URI,
+        target: &str,
+        source: &Source,
+    ) -> Option<Source> {
+        if let Some(path) = target.strip_prefix("file://") {
+            return Some(Source::File(PathBuf::from(percent_decode(path))));
+        }
+        let directory = match source {
+            Source::File(file) => file.parent().unwrap_or_else(|| Path::new("")),
+            Source::Inline(directory) => directory,
+            Source::Bundled(..) => return self.registry.locate(target),
+        };
+        if reference.is_relative_reference() {
+            let path = reference.path().to_string();
+            return Some(Source::File(directory.join(percent_decode(&path))));
+        }
+        if let Some(found) = self.registry.locate(target) {
+            return Some(found);
+        }
+        // An absolute URI below the base URI of the referring document
+        let base = base.to_string();
+        let base_directory = &base[..base.rfind('/')? + 1];
+        let path = target.strip_prefix(base_directory)?;
+        Some(Source::File(directory.join(percent_decode(path))))
+    }
+
+    fn alias(&mut self, uri: &str, key: &str) {
+        self.aliases
+            .entry(document_uri(uri))
+            .or_insert_with(|| key.to_owned());
+    }
+
+    fn parse(
+        &mut self,
+        key: &str,
+        base: &str,
+        source: &Source,
+        document: &mut Value,
+        draft: Draft,
+    ) -> Result<Schema, Error> {
+        draft.normalize_document(document);
+        walk_subschemas_mut(document, &mut |schema| {
+            if let Some(Value::String(ref_)) = schema.get_mut("$ref") {
+                if let Some(resolved) = self.resolve(key, base, source, ref_) {
+                    *ref_ = resolved;
+                }
+            }
+        });
+        serde_json::from_value(document.clone()).map_err(|source_err| Error::Schema {
+            file: source.file(),
+            source: source_err,
+        })
+    }
+
+    /// Rewrites the reference `ref_` found in the document `key` (read from `source`) to the
+    /// document it refers to, queueing that document to be loaded if it has not been seen yet.
+    ///
+    /// Returns `None` if the reference should be left as it is.
+    fn resolve(&mut self, key: &str, base: &str, source: &Source, ref_: &str) -> Option<String> {
+        if let Some(fragment) = ref_.strip_prefix('#') {
+            return if key == self.root {
+                None
+            } else {
+                Some(format!("{}#{}", key, fragment))
+            };
+        }
+
+        let reference = URIReference::try_from(ref_).ok()?;
+        let base = URI::try_from(base).ok()?;
+        let target = base.resolve(&reference);
+        let fragment = target.fragment().map_or("", Fragment::as_str).to_owned();
+        let target = document_uri(&target.to_string());
+
+        let target = match self.aliases.get(&target) {
+            Some(key) => key.clone(),
+            None => {
+                let found = self.locate(&reference, &base, &target, source)?;
+                let uri = match found {
+                    Source::File(ref path) => file_uri(path),
+                    Source::Inline(ref directory) => file_uri(directory),
+                    Source::Bundled(ref uri, _) => uri.clone(),
+                };
+                let key = match self.aliases.get(&uri) {
+                    Some(key) => key.clone(),
+                    None => {
+                        self.queue.push((target.clone(), found));
+                        target.clone()
+                    }
+                };
+                self.alias(&uri, &key);
+                self.alias(&target, &key);
+                key
+            }
+        };
+
+        if target == self.root {
+            Some(format!("#{}", fragment))
+        } else {
+            if fragment.is_empty() {
+                self.referenced.insert(target.clone());
+            }
+            Some(format!("{}#{}", target, fragment))
+        }
+    }
+}
+
+fn read_json(source: &Source) -> Result<Value, Error> {
+    let json = match source {
+        Source::File(file) => std::fs::read_to_string(file).map_err(|err| Error::Io {
+            file: file.clone(),
+            source: err,
+        })?,
+        Source::Bundled(_, json) => json.to_string(),
+        Source::Inline(_) => unreachable!("Schemas passed in memory are never read"),
+    };
+    serde_json::from_str(&json).map_err(|err| Error::Json {
+        file: source.file(),
+        source: err,
+    })
+}
+
+fn detect_draft(document: &Value, source: &Source) -> Result<Option<Draft>, Error> {
+    Draft::detect(document).map_err(|err| Error::UnsupportedDialect {
+        file: source.file(),
+        source: err,
+    })
+}
+
+/// The base URI of `document`, which is its `$id` (resolved against the URI it was retrieved
+/// from) if it has one.
+fn base_uri(document: &Value, draft: Draft, retrieval_uri: &str) -> String {
+    document
+        .get(draft.id_keyword())
+        .and_then(Value::as_str)
+        .and_then(|id| {
+            let id = URIReference::try_from(id).ok()?;
+            let retrieval_uri = URI::try_from(retrieval_uri).ok()?;
+            Some(retrieval_uri.resolve(&id).to_string())
+        })
+        .unwrap_or_else(|| retrieval_uri.to_owned())
+}
+
+/// Strips the fragment from `uri`.
+fn document_uri(uri: &str) -> String {
+    uri.split('#').next().unwrap_or(uri).to_owned()
+}
+
+/// The `file://` URI of the file at `path`.
+fn file_uri(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.as_bytes().iter();
+    while let Some(&byte) = iter.next() {
+        let hex = iter
+            .as_slice()
+            .get(..2)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+        match hex {
+            Some(hex) if byte == b'%' => {
+                let hex = std::str::from_utf8(hex).unwrap();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap());
+                iter.nth(1);
+                continue;
+            }
+            _ => (),
+        }
+        bytes.push(byte);
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::Schema;
+
+/// A version (dialect) of the JSON Schema specification.
+///
+/// Drafts are ordered by their release, so `draft >= Draft::Draft201909` can be used to check
+/// for behaviour introduced in a specific draft.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Draft {
+    #[default]
+    Draft4,
+    Draft6,
+    Draft7,
+    Draft201909,
+    Draft202012,
+}
+
+/// The error returned when `$schema` names a dialect which is not supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedDialect(pub String);
+
+impl fmt::Display for UnsupportedDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unsupported JSON Schema dialect `{}`, expected draft 4, 6, 7, 2019-09 or 2020-12",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedDialect {}
+
+impl Draft {
+    /// Returns the draft identified by the `$schema` URI `uri`.
+    pub fn from_uri(uri: &str) -> Option<Draft> {
+        let uri = uri.trim_end_matches('#');
+        let path = uri
+            .strip_prefix("http://")
+            .or_else(|| uri.strip_prefix("https://"))?;
+        Some(match path {
+            "json-schema.org/draft-04/schema" => Draft::Draft4,
+            "json-schema.org/draft-06/schema" => Draft::Draft6,
+            "json-schema.org/draft-07/schema" => Draft::Draft7,
+            "json-schema.org/draft/2019-09/schema" => Draft::Draft201909,
+            "json-schema.org/draft/2020-12/schema" => Draft::Draft202012,
+            _ => return None,
+        })
+    }
+
+    /// Returns the draft declared by the `$schema` keyword of a schema document, or `None` if
+    /// the document does not declare one.
+    pub fn detect(document: &Value) -> Result<Option<Draft>, UnsupportedDialect> {
+        match document.get("$schema") {
+            Some(Value::String(uri)) => Draft::from_uri(uri)
+                .map(Some)
+                .ok_or_else(|| UnsupportedDialect(uri.clone())),
+            Some(value) => Err(UnsupportedDialect(value.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads a schema document written in this draft into the [`Schema`] model.
+    ///
+    /// Keywords which are spelled differently between drafts are rewritten to a single form
+    /// first, so the `Expander` does not need to know about every spelling.
+    ///
+    /// Boolean schemas are read as the equivalent object schemas, `true` as `{}` and `false` as
+    /// `{"not": {}}`.
+    pub fn parse(self, mut document: Value) -> serde_json::Result<Schema> {
+        self.normalize_document(&mut document);
+        serde_json::from_value(document)
+    }
+
+    /// Rewrites `document` in place to the form [`Draft::parse`] reads into the `Schema` model.
+    pub(crate) fn normalize_document(self, document: &mut Value) {
+        if let Value::Bool(valid) = *document {
+            *document = boolean_schema(valid);
+        }
+        walk_subschemas_mut(document, &mut |schema| {
+            if let Value::Object(schema) = schema {
+                self.normalize(schema);
+                expand_boolean_schemas(schema);
+            }
+        });
+    }
+
+    /// The keyword which holds the base URI of a schema.
+    pub(crate) fn id_keyword(self) -> &'static str {
+        if self == Draft::Draft4 {
+            "id"
+        } else {
+            "$id"
+        }
+    }
+
+    fn normalize(self, schema: &mut Map<String, Value>) {
+        match self {
+            Draft::Draft4 | Draft::Draft6 | Draft::Draft7 => {
+                // A plain name fragment as the id is what later drafts call an `$anchor`
+                let id = self.id_keyword();
+                let anchor = schema
+                    .get(id)
+                    .and_then(Value::as_str)
+                    .and_then(|id| id.strip_prefix('#'))
+                    .filter(|anchor| !anchor.is_empty())
+                    .map(|anchor| anchor.to_owned());
+                if let Some(anchor) = anchor {
+                    schema.remove(id);
+                    schema.insert("$anchor".into(), Value::String(anchor));
+                }
+            }
+            // Without dynamic scopes the recursive and dynamic references resolve like plain
+            // references (to the schema or anchor in the same document)
+            Draft::Draft201909 => rename_keyword(schema, "$recursiveRef", "$ref"),
+            Draft::Draft202012 => {
+                rename_keyword(schema, "$dynamicRef", "$ref");
+                rename_keyword(schema, "$dynamicAnchor", "$anchor");
+            }
+        }
+    }
+}
+
+fn rename_keyword(schema: &mut Map<String, Value>, from: &str, to: &str) {
+    if schema.contains_key(to) {
+        return;
+    }
+    if let Some(value) = schema.remove(from) {
+        schema.insert(to.into(), value);
+    }
+}
+
+/// The object schema equivalent to the boolean schema `valid`.
+fn boolean_schema(valid: bool) -> Value {
+    if valid {
+        Value::Object(Map::new())
+    } else {
+        json!({ "not": {} })
+    }
+}
+
+/// Replaces the boolean subschemas of `schema` with object schemas.
+///
+/// The keywords in `BOOLEAN_KEYWORDS` are left alone as the `Schema` model stores them as a
+/// `Value`.
+fn expand_boolean_schemas(schema: &mut Map<String, Value>) {
+    let expand = |value: &mut Value| {
+        if let Value::Bool(valid) = *value {
+            *value = boolean_schema(valid);
+        }
+    };
+    for (keyword, value) in schema.iter_mut() {
+        let keyword = keyword.as_str();
+        match value {
+            Value::Bool(_)
+                if SCHEMA_KEYWORDS.contains(&keyword) && !BOOLEAN_KEYWORDS.contains(&keyword) =>
+            {
+                expand(value)
+            }
+            Value::Array(values) if SCHEMA_ARRAY_KEYWORDS.contains(&keyword) => {
+                values.iter_mut().for_each(expand)
+            }
+            Value::Object(values) if SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                values.values_mut().for_each(expand)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Keywords whose (boolean) subschema is kept as a `Value` in the `Schema` model.
+const BOOLEAN_KEYWORDS: &[&str] = &[
+    "additionalItems",
+    "additionalProperties",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords whose value is a single subschema.
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "additionalItems",
+    "additionalProperties",
+    "contains",
+    "else",
+    "if",
+    "items",
+    "not",
+    "propertyNames",
+    "then",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords whose value is an array of subschemas.
+const SCHEMA_ARRAY_KEYWORDS: &[&str] = &["allOf", "anyOf", "items", "oneOf", "prefixItems"];
+
+/// Keywords whose value is an object with subschemas as values.
+const SCHEMA_MAP_KEYWORDS: &[&str] = &[
+    "$defs",
+    "definitions",
+    "dependencies",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+/// Calls `f` on `schema` and then on each of its subschemas, recursively.
+///
+/// Values of keywords which are not schemas, such as `enum` or `default`, are not visited.
+pub(crate) fn walk_subschemas_mut(schema: &mut Value, f: &mut dyn FnMut(&mut Value)) {
+    f(schema);
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+    for (keyword, value) in schema.iter_mut() {
+        let keyword = keyword.as_str();
+        match value {
+            Value::Array(values) if SCHEMA_ARRAY_KEYWORDS.contains(&keyword) => {
+                for value in values {
+                    walk_subschemas_mut(value, f);
+                }
+            }
+            // `dependencies` may also map to an array of property names
+            Value::Object(values) if SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                for value in values.values_mut().filter(|value| !value.is_array()) {
+                    walk_subschemas_mut(value, f);
+                }
+            }
+            Value::Object(_) | Value::Bool(_) if SCHEMA_KEYWORDS.contains(&keyword) => {
+                walk_subschemas_mut(value, f);
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Enums whose values are not all strings, which `serde` can not derive.
+
+use std::collections::BTreeSet;
+
+use inflector::Inflector;
+use proc_macro2::{Span, TokenStream};
+use serde_json::Value;
+
+/// The name of the variant for the enum value `value`, when the schema does not give one with
+/// `enumNames`.
+///
+/// Numbers become `V` followed by the number, spelling out the characters which can not appear
+/// in an identifier: `-1.5` becomes `VMinus1Point5`.
+pub(crate) fn variant_name(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_pascal_case(),
+        Value::Bool(true) => "True".into(),
+        Value::Bool(false) => "False".into(),
+        Value::Number(n) => format!(
+            "V{}",
+            n.to_string()
+                .replace('-', "Minus")
+                .replace('+', "")
+                .replace('.', "Point")
+        ),
+        _ => value.to_string(),
+    }
+}
+
+/// `name`, or `name` with the first number from 2 which makes it unique if it is in `names`
+/// already. The name is added to `names`.
+pub(crate) fn unique_name(name: String, names: &mut BTreeSet<String>) -> String {
+    let name = if names.contains(&name) {
+        (2..)
+            .map(|i| format!("{}{}", name, i))
+            .find(|name| !names.contains(name))
+            .unwrap_or(name)
+    } else {
+        name
+    };
+    names.insert(name.clone());
+    name
+}
+
+/// `Serialize` and `Deserialize` implementations for the enum `name`, which (de)serialize each
+/// of the `variants` as its literal value.
+pub(crate) fn literal_impls(
+    name: &syn::Ident,
+    variants: &[(syn::Ident, &Value)],
+    serde_path: &TokenStream,
+    value_type: &TokenStream,
+) -> TokenStream {
+    let (idents, values): (Vec<_>, Vec<_>) = variants.iter().cloned().unzip();
+    let serialized = values.iter().map(|value| match value {
+        Value::String(s) => quote!(serializer.serialize_str(#s)),
+        Value::Bool(b) => quote!(serializer.serialize_bool(#b)),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => quote!(serializer.serialize_u64(#u)),
+            (None, Some(i)) => quote!(serializer.serialize_i64(#i)),
+            _ => {
+                let f = n.as_f64().unwrap_or_default();
+                quote!(serializer.serialize_f64(#f))
+            }
+        },
+        _ => unreachable!("only strings, numbers and booleans are enum variants"),
+    });
+    // Numbers are compared by value, so `1.0` is the variant `1`
+    let matches = values.iter().map(|value| match value {
+        Value::String(s) => quote!(value.as_str() == Some(#s)),
+        Value::Bool(b) => quote!(value.as_bool() == Some(#b)),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => quote! {
+                value.as_u64() == Some(#u) || value.is_f64() && value.as_f64() == Some(#u as f64)
+            },
+            (None, Some(i)) => quote! {
+                value.as_i64() == Some(#i) || value.is_f64() && value.as_f64() == Some(#i as f64)
+            },
+            _ => {
+                let f = n.as_f64().unwrap_or_default();
+                quote!(value.as_f64() == Some(#f))
+            }
+        },
+        _ => unreachable!("only strings, numbers and booleans are enum variants"),
+    });
+    let expected = values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let expected = syn::LitStr::new(&format!("one of {}", expected), Span::call_site());
+    quote! {
+        impl #serde_path::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: #serde_path::Serializer,
+            {
+                match *self {
+                    #(#name::#idents => #serialized,)*
+                }
+            }
+        }
+        impl<'de> #serde_path::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: #serde_path::Deserializer<'de>,
+            {
+                let value = <#value_type as #serde_path::Deserialize>::deserialize(deserializer)?;
+                #(
+                    if #matches {
+                        return Ok(#name::#idents);
+                    }
+                )*
+                Err(<D::Error as #serde_path::de::Error>::invalid_value(
+                    #serde_path::de::Unexpected::Other(&value.to_string()),
+                    &#expected,
+                ))
+            }
+        }
+    }
+}
+
+/// `Serialize` and `Deserialize` implementations for the `#[repr(i64)]` enum `name`, which
+/// (de)serialize each of the `variants` as its integer value like the derives of `serde_repr`.
+///
+/// Unlike those derives, they refer to `serde` through `serde_path`, which need not be in scope
+/// where the generated code is included.
+pub(crate) fn repr_impls(
+    name: &syn::Ident,
+    variants: &[(syn::Ident, &Value)],
+    serde_path: &TokenStream,
+) -> TokenStream {
+    let (idents, values): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .filter_map(|(ident, value)| Some((ident, value.as_i64()?)))
+        .unzip();
+    let expected = values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let expected = syn::LitStr::new(&format!("one of {}", expected), Span::call_site());
+    quote! {
+        impl #serde_path::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: #serde_path::Serializer,
+            {
+                let value: i64 = match *self {
+                    #(#name::#idents => #values,)*
+                };
+                serializer.serialize_i64(value)
+            }
+        }
+        impl<'de> #serde_path::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: #serde_path::Deserializer<'de>,
+            {
+                match <i64 as #serde_path::Deserialize>::deserialize(deserializer)? {
+                    #(#values => Ok(#name::#idents),)*
+                    other => Err(<D::Error as #serde_path::de::Error>::invalid_value(
+                        #serde_path::de::Unexpected::Signed(other),
+                        &#expected,
+                    )),
+                }
+            }
+        }
+    }
+}
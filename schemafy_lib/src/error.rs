@@ -0,0 +1,352 @@
+use std::{fmt, io, path::PathBuf};
+
+use serde_json::Value;
+
+use crate::UnsupportedDialect;
+
+/// Where in a schema an error occurred.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// The file the schema was read from, `None` for a schema passed to the `Expander` directly.
+    pub file: Option<PathBuf>,
+    /// The JSON pointer to the failing subschema within the file, `""` for the whole file.
+    pub pointer: String,
+}
+
+impl Location {
+    /// The line and column (both starting at 1) of the failing subschema in `file`.
+    ///
+    /// Returns `None` if the file can not be read or does not contain the subschema.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        let json = std::fs::read_to_string(self.file.as_ref()?).ok()?;
+        pointer_position(&json, &self.pointer)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref file) = self.file {
+            write!(f, "{}", file.to_string_lossy())?;
+        }
+        write!(f, "#{}", self.pointer)
+    }
+}
+
+/// The error returned when types can not be generated for a schema.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A schema file could not be read.
+    Io { file: PathBuf, source: io::Error },
+    /// A schema file is not valid JSON.
+    Json {
+        file: PathBuf,
+        source: serde_json::Error,
+    },
+    /// A schema file is JSON, but not a JSON schema.
+    Schema {
+        file: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The `$schema` of a schema file names a dialect which is not supported.
+    UnsupportedDialect {
+        file: PathBuf,
+        source: UnsupportedDialect,
+    },
+    /// A `$ref` does not point to a schema.
+    UnresolvedRef {
+        location: Location,
+        reference: String,
+    },
+    /// The root schema is referenced but no root name was given to generate it under.
+    MissingRootName { location: Location },
+    /// `enumNames` and `enum` list a different number of values.
+    EnumNamesLength {
+        location: Location,
+        names: usize,
+        values: usize,
+    },
+    /// An `enum` value which can not be turned into an enum variant.
+    InvalidEnumValue { location: Location, value: Value },
+    /// A subschema stored as JSON, such as the value of `additionalProperties`, is not a JSON
+    /// schema.
+    InvalidSubschema {
+        location: Location,
+        source: serde_json::Error,
+    },
+    /// The types of two documents have the same name, even qualified with the paths of the
+    /// documents. `location` is the definition (or document) named last.
+    TypeNameClash { location: Location, name: String },
+    /// An option, such as the visibility or a derive, is not valid Rust.
+    InvalidOption {
+        option: String,
+        value: String,
+        message: String,
+    },
+    /// The code generated for a subschema is not valid Rust, because of the options it is made
+    /// of (such as the type a `format` is mapped to).
+    InvalidCode {
+        location: Location,
+        code: String,
+        message: String,
+    },
+    /// The generated code could not be formatted with rustfmt.
+    Rustfmt { source: io::Error },
+    /// The generated code is not a valid Rust file, so it could not be formatted or given the
+    /// comparison derives in-process.
+    Format { source: syn::Error },
+}
+
+impl Error {
+    /// The file and JSON pointer of the failing schema.
+    pub fn location(&self) -> Location {
+        match self {
+            Error::Io { file, .. } | Error::Json { file, .. } | Error::Schema { file, .. } => {
+                Location {
+                    file: Some(file.clone()),
+                    pointer: String::new(),
+                }
+            }
+            Error::UnsupportedDialect { file, .. } => Location {
+                file: Some(file.clone()),
+                pointer: "/$schema".into(),
+            },
+            Error::UnresolvedRef { location, .. }
+            | Error::MissingRootName { location }
+            | Error::EnumNamesLength { location, .. }
+            | Error::InvalidEnumValue { location, .. }
+            | Error::InvalidSubschema { location, .. }
+            | Error::TypeNameClash { location, .. }
+            | Error::InvalidCode { location, .. } => location.clone(),
+            Error::InvalidOption { .. } | Error::Rustfmt { .. } | Error::Format { .. } => {
+                Location {
+                    file: None,
+                    pointer: String::new(),
+                }
+            }
+        }
+    }
+
+    /// The line and column (both starting at 1) of the error in its file, if known.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Io { .. }
+            | Error::InvalidOption { .. }
+            | Error::Rustfmt { .. }
+            | Error::Format { .. } => None,
+            Error::Json { source, .. } => Some((source.line(), source.column())),
+            _ => self.location().position(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { file, source } => {
+                write!(f, "Unable to read `{}`: {}", file.to_string_lossy(), source)
+            }
+            Error::Json { file, source } => write!(
+                f,
+                "Cannot parse `{}` as JSON: {}",
+                file.to_string_lossy(),
+                source
+            ),
+            Error::Schema { file, source } => write!(
+                f,
+                "Cannot parse `{}` as a JSON schema: {}",
+                file.to_string_lossy(),
+                source
+            ),
+            Error::UnsupportedDialect { file, source } => {
+                write!(f, "{} in `{}`", source, file.to_string_lossy())
+            }
+            Error::UnresolvedRef {
+                location,
+                reference,
+            } => write!(
+                f,
+                "Expected definition: `{}` (referenced at `{}`)",
+                reference, location
+            ),
+            Error::MissingRootName { location } => write!(
+                f,
+                "No root name specified for schema (referenced at `{}`)",
+                location
+            ),
+            Error::EnumNamesLength {
+                location,
+                names,
+                values,
+            } => write!(
+                f,
+                "enumNames(length {}) and enum(length {}) have different length at `{}`",
+                names, values, location
+            ),
+            Error::InvalidEnumValue { location, value } => write!(
+                f,
+                "Expected a string, number, boolean or null for enum got `{}` at `{}`",
+                value, location
+            ),
+            Error::InvalidSubschema { location, source } => {
+                write!(f, "Invalid subschema at `{}`: {}", location, source)
+            }
+            Error::TypeNameClash { location, name } => write!(
+                f,
+                "The type `{}` of `{}` clashes with a type of another document",
+                name, location
+            ),
+            Error::InvalidOption {
+                option,
+                value,
+                message,
+            } => write!(f, "Invalid {} `{}`: {}", option, value, message),
+            Error::InvalidCode {
+                location,
+                code,
+                message,
+            } => write!(
+                f,
+                "Generated invalid code `{}` at `{}`: {}",
+                code, location, message
+            ),
+            Error::Rustfmt { source } => {
+                write!(f, "Unable to format the generated code: {}", source)
+            }
+            Error::Format { source } => {
+                write!(f, "The generated code is not a valid Rust file: {}", source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } | Error::Rustfmt { source } => Some(source),
+            Error::Json { source, .. }
+            | Error::Schema { source, .. }
+            | Error::InvalidSubschema { source, .. } => Some(source),
+            Error::UnsupportedDialect { source, .. } => Some(source),
+            Error::Format { source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the line and column of the value at the JSON pointer `pointer` in the JSON text `json`.
+fn pointer_position(json: &str, pointer: &str) -> Option<(usize, usize)> {
+    let bytes = json.as_bytes();
+    let mut pos = skip_whitespace(bytes, 0);
+    let tokens = pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"));
+    for token in tokens {
+        match bytes.get(pos)? {
+            b'{' => {
+                pos += 1;
+                loop {
+                    pos = skip_whitespace(bytes, pos);
+                    let key_end = string_end(bytes, pos)?;
+                    let key: String = serde_json::from_str(&json[pos..key_end]).ok()?;
+                    pos = skip_whitespace(bytes, key_end);
+                    if bytes.get(pos)? != &b':' {
+                        return None;
+                    }
+                    pos = skip_whitespace(bytes, pos + 1);
+                    if key == token {
+                        break;
+                    }
+                    pos = skip_whitespace(bytes, value_end(bytes, pos)?);
+                    if bytes.get(pos)? != &b',' {
+                        return None;
+                    }
+                    pos += 1;
+                }
+            }
+            b'[' => {
+                pos = skip_whitespace(bytes, pos + 1);
+                for _ in 0..token.parse::<usize>().ok()? {
+                    pos = skip_whitespace(bytes, value_end(bytes, pos)?);
+                    if bytes.get(pos)? != &b',' {
+                        return None;
+                    }
+                    pos = skip_whitespace(bytes, pos + 1);
+                }
+            }
+            _ => return None,
+        }
+    }
+    let before = &json[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next()?.chars().count() + 1;
+    Some((line, column))
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos += 1;
+    }
+    pos
+}
+
+/// The position after the string starting at `pos`.
+fn string_end(bytes: &[u8], mut pos: usize) -> Option<usize> {
+    if bytes.get(pos)? != &b'"' {
+        return None;
+    }
+    pos += 1;
+    loop {
+        match bytes.get(pos)? {
+            b'\\' => pos += 2,
+            b'"' => return Some(pos + 1),
+            _ => pos += 1,
+        }
+    }
+}
+
+/// The position after the value starting at `pos`.
+fn value_end(bytes: &[u8], mut pos: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    loop {
+        match bytes.get(pos) {
+            Some(b'"') => pos = string_end(bytes, pos)?,
+            Some(b'{') | Some(b'[') => {
+                depth += 1;
+                pos += 1;
+            }
+            Some(b'}') | Some(b']') if depth > 0 => {
+                depth -= 1;
+                pos += 1;
+            }
+            // The end of a number, `true`, `false` or `null`
+            Some(b',') | Some(b'}') | Some(b']') | None if depth == 0 => return Some(pos),
+            None => return None,
+            Some(byte) if depth == 0 && byte.is_ascii_whitespace() => return Some(pos),
+            Some(_) => pos += 1,
+        }
+        if depth == 0 && matches!(bytes[pos - 1], b'"' | b'}' | b']') {
+            return Some(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let json = r#"{
+    "a": [1, "x,]", { "b": true }],
+    "c~/d": { "e": null }
+}"#;
+        assert_eq!(pointer_position(json, ""), Some((1, 1)));
+        assert_eq!(pointer_position(json, "/a"), Some((2, 10)));
+        assert_eq!(pointer_position(json, "/a/2/b"), Some((2, 28)));
+        assert_eq!(pointer_position(json, "/c~0~1d/e"), Some((3, 20)));
+        assert_eq!(pointer_position(json, "/missing"), None);
+        assert_eq!(pointer_position(json, "/a/3"), None);
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use proc_macro2::TokenStream;
+
+/// Pretty prints generated code, without depending on an external rustfmt.
+///
+/// Fails if the tokens are not a valid Rust file.
+pub(crate) fn pretty_print(tokens: &TokenStream) -> syn::Result<String> {
+    let file = syn::parse2::<syn::File>(tokens.clone())?;
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Formats `code` with the `rustfmt` found in `PATH`.
+pub(crate) fn rustfmt(code: &str) -> io::Result<String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2018"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin");
+    let input = code.to_owned();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().expect("rustfmt input")?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "rustfmt exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_code() {
+        assert!(pretty_print(&quote!(
+            struct Point {
+                x: f64,
+            }
+        ))
+        .is_ok());
+        assert!(pretty_print(&quote!(struct Point { x: })).is_err());
+    }
+}
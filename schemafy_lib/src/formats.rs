@@ -0,0 +1,102 @@
+//! The Rust types of the string `format`s, from the crates chosen with `FormatCrate`.
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// A crate whose types strings with a `format` are generated as, instead of `String`.
+///
+/// The generated code refers to the crates through `schemafy_path`, so the feature of
+/// `schemafy_core` with the same name as the crate has to be enabled. `Net` uses the types of
+/// `std::net` and needs no feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum FormatCrate {
+    /// `chrono` for `date-time`, `date` and `time`.
+    Chrono,
+    /// `time` for `date-time`, `date` and `time`.
+    Time,
+    /// `uuid` for `uuid`.
+    Uuid,
+    /// `url` for `uri`.
+    Url,
+    /// `email_address` for `email`.
+    Email,
+    /// `std::net` for `ipv4` and `ipv6`.
+    Net,
+}
+
+impl FormatCrate {
+    /// Every crate, in the order of their names.
+    pub const ALL: [FormatCrate; 6] = [
+        FormatCrate::Chrono,
+        FormatCrate::Email,
+        FormatCrate::Net,
+        FormatCrate::Time,
+        FormatCrate::Url,
+        FormatCrate::Uuid,
+    ];
+
+    /// The name of the crate, which is also the name of its feature of `schemafy_core`.
+    pub fn name(self) -> &'static str {
+        match self {
+            FormatCrate::Chrono => "chrono",
+            FormatCrate::Time => "time",
+            FormatCrate::Uuid => "uuid",
+            FormatCrate::Url => "url",
+            FormatCrate::Email => "email",
+            FormatCrate::Net => "net",
+        }
+    }
+}
+
+impl fmt::Display for FormatCrate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FormatCrate {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        FormatCrate::ALL
+            .iter()
+            .copied()
+            .find(|format_crate| format_crate.name() == name)
+            .ok_or_else(|| {
+                let names = FormatCrate::ALL
+                    .iter()
+                    .map(|format_crate| format!("`{}`", format_crate))
+                    .collect::<Vec<_>>();
+                format!("expected one of {}", names.join(", "))
+            })
+    }
+}
+
+/// The type of strings with `format`, if one of `crates` maps it to one.
+pub(crate) fn format_type(
+    format: &str,
+    crates: &BTreeSet<FormatCrate>,
+    schemafy_path: &str,
+) -> Option<String> {
+    let uses = |format_crate| crates.contains(&format_crate);
+    let typ = match format {
+        "date-time" if uses(FormatCrate::Chrono) => {
+            format!("{0}chrono::DateTime<{0}chrono::FixedOffset>", schemafy_path)
+        }
+        "date-time" if uses(FormatCrate::Time) => format!("{}Rfc3339", schemafy_path),
+        "date" if uses(FormatCrate::Chrono) => format!("{}chrono::NaiveDate", schemafy_path),
+        "date" if uses(FormatCrate::Time) => format!("{}time::Date", schemafy_path),
+        // Neither crate has a type for a time of day with an offset
+        "time" if uses(FormatCrate::Chrono) => format!("{}ChronoTime", schemafy_path),
+        "time" if uses(FormatCrate::Time) => format!("{}Rfc3339Time", schemafy_path),
+        "uuid" if uses(FormatCrate::Uuid) => format!("{}uuid::Uuid", schemafy_path),
+        "uri" if uses(FormatCrate::Url) => format!("{}url::Url", schemafy_path),
+        "email" if uses(FormatCrate::Email) => {
+            format!("{}email_address::EmailAddress", schemafy_path)
+        }
+        "ipv4" if uses(FormatCrate::Net) => "::std::net::Ipv4Addr".into(),
+        "ipv6" if uses(FormatCrate::Net) => "::std::net::Ipv6Addr".into(),
+        _ => return None,
+    };
+    Some(typ)
+}
@@ -1,5 +1,10 @@
-use crate::Expander;
+use crate::{
+    documents::Documents, format, registry::INLINE_SCHEMA, Draft, Error, Expander, FormatCrate,
+    NumberType, Registry, Schema,
+};
+use serde_json::Value;
 use std::{
+    collections::{BTreeMap, BTreeSet},
     io,
     path::{Path, PathBuf},
 };
@@ -21,8 +26,51 @@ pub struct Generator<'a, 'b> {
     /// re-exported this crate or imported it under a different name,
     /// the default should be fine.
     pub schemafy_path: &'a str,
+    /// The path to the `serde` crate used by the generated code. If
+    /// `None` the `serde` re-exported by `schemafy_core` is used, so
+    /// the crate including the code does not need to depend on it.
+    pub serde_path: Option<&'a str>,
     /// The JSON schema file to read
     pub input_file: &'b Path,
+    /// The JSON Schema draft the schema is written in. If `None` the
+    /// draft is detected from the `$schema` keyword, defaulting to
+    /// draft 4 for schemas which do not declare one.
+    pub draft: Option<Draft>,
+    /// Maps the absolute URIs of the schemas referenced with `$ref` to
+    /// local files.
+    pub registry: Registry,
+    /// Make the generated code depend on the schema files it was generated
+    /// from (through `include_bytes!`), so the crate is rebuilt when they
+    /// change. Used by the `schemafy!` macro.
+    pub track_dependencies: bool,
+    /// Changes to the derives and attributes of every generated type.
+    pub type_options: TypeOptions,
+    /// Changes to the derives and attributes of single generated types,
+    /// keyed by the name of the type.
+    pub named_type_options: BTreeMap<String, TypeOptions>,
+    /// The visibility of the generated types, and of their fields unless
+    /// `field_visibility` is set.
+    pub visibility: String,
+    /// The visibility of the fields of the generated structs.
+    pub field_visibility: Option<String>,
+    /// The map type used for objects without fixed properties.
+    pub map_type: String,
+    /// The types of strings with a `format`, keyed by the format. These
+    /// take precedence over the types of `format_crates`.
+    pub formats: BTreeMap<String, String>,
+    /// The crates whose types strings with a `format` are generated as.
+    pub format_crates: BTreeSet<FormatCrate>,
+    /// Reject unknown fields in every struct unless the schema allows
+    /// them explicitly.
+    pub strict: bool,
+    /// Pick the narrowest integer type which fits the bounds and `format`
+    /// of each integer schema, instead of `i64`.
+    pub narrow_integers: bool,
+    /// The type of `"type": "number"`, unless its `format` is `decimal`.
+    pub number_type: NumberType,
+    /// Format the generated code with an external `rustfmt` after
+    /// formatting it in-process.
+    pub rustfmt: bool,
 }
 
 impl<'a, 'b> Generator<'a, 'b> {
@@ -31,41 +79,165 @@ impl<'a, 'b> Generator<'a, 'b> {
         GeneratorBuilder::default()
     }
 
+    /// Generates the types, panicking if the schema can not be read or expanded.
     pub fn generate(&self) -> proc_macro2::TokenStream {
+        self.try_generate().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Generates the types, returning an error if the schema can not be read or expanded.
+    pub fn try_generate(&self) -> Result<proc_macro2::TokenStream, Error> {
+        let documents = self.load()?;
+        self.expand(&documents)
+    }
+
+    /// Reads `input_file` and the documents it references.
+    pub(crate) fn load(&self) -> Result<Documents, Error> {
         let input_file = if self.input_file.is_relative() {
-            let crate_root = get_crate_root().unwrap();
+            let crate_root = get_crate_root().map_err(|err| Error::Io {
+                file: self.input_file.to_owned(),
+                source: err,
+            })?;
             crate_root.join(self.input_file)
         } else {
             PathBuf::from(self.input_file)
         };
 
-        let json = std::fs::read_to_string(&input_file).unwrap_or_else(|err| {
-            panic!("Unable to read `{}`: {}", input_file.to_string_lossy(), err)
-        });
+        Documents::load(&input_file, self.draft, &self.registry)
+    }
+
+    /// Generates the types for the schema in the string `json` instead of
+    /// `input_file`, panicking if it can not be read or expanded.
+    pub fn generate_from_str(&self, json: &str) -> proc_macro2::TokenStream {
+        self.try_generate_from_str(json)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Generates the types for the schema in the string `json` instead of
+    /// `input_file`.
+    ///
+    /// Relative `$ref`s to other files are resolved against the crate root.
+    pub fn try_generate_from_str(&self, json: &str) -> Result<proc_macro2::TokenStream, Error> {
+        let document = serde_json::from_str(json).map_err(|err| Error::Json {
+            file: PathBuf::from(INLINE_SCHEMA),
+            source: err,
+        })?;
+        self.try_generate_from_value(document)
+    }
+
+    /// Generates the types for the schema `document` instead of
+    /// `input_file`.
+    ///
+    /// Relative `$ref`s to other files are resolved against the crate root.
+    pub fn try_generate_from_value(
+        &self,
+        document: Value,
+    ) -> Result<proc_macro2::TokenStream, Error> {
+        let crate_root = get_crate_root().map_err(|err| Error::Io {
+            file: PathBuf::from(INLINE_SCHEMA),
+            source: err,
+        })?;
+        let documents = Documents::from_value(document, &crate_root, self.draft, &self.registry)?;
+        self.expand(&documents)
+    }
+
+    /// Generates the types for `schema` instead of `input_file`.
+    ///
+    /// Relative `$ref`s to other files are resolved against the crate root.
+    pub fn try_generate_from_schema(
+        &self,
+        schema: &Schema,
+    ) -> Result<proc_macro2::TokenStream, Error> {
+        let document = serde_json::to_value(schema).map_err(|err| Error::Schema {
+            file: PathBuf::from(INLINE_SCHEMA),
+            source: err,
+        })?;
+        self.try_generate_from_value(document)
+    }
+
+    pub(crate) fn expand(&self, documents: &Documents) -> Result<proc_macro2::TokenStream, Error> {
+        let mut expander = Expander::new(
+            self.root_name.as_deref(),
+            self.schemafy_path,
+            &documents.root,
+        )
+        .with_draft(documents.draft);
+        if let Some(serde_path) = self.serde_path {
+            expander = expander.with_serde_path(serde_path);
+        }
+        if let Some(ref visibility) = self.field_visibility {
+            expander = expander.with_field_visibility(visibility);
+        }
+        for (format, typ) in &self.formats {
+            expander = expander.with_format(format, typ);
+        }
+        for &format_crate in &self.format_crates {
+            expander = expander.with_format_crate(format_crate);
+        }
+        for (name, options) in &self.named_type_options {
+            expander = expander.with_named_type_options(name, options.clone());
+        }
+        let mut expander = expander
+            .with_type_options(self.type_options.clone())
+            .with_visibility(&self.visibility)
+            .with_map_type(&self.map_type)
+            .with_strict(self.strict)
+            .with_narrow_integers(self.narrow_integers)
+            .with_number_type(self.number_type)
+            .with_documents(documents);
+        let tokens = expander.try_expand_root()?;
+        if !self.track_dependencies {
+            return Ok(tokens);
+        }
 
-        let schema = serde_json::from_str(&json).unwrap_or_else(|err| {
-            panic!(
-                "Cannot parse `{}` as JSON: {}",
-                input_file.to_string_lossy(),
-                err
-            )
+        let files = documents.files.iter().map(|file| {
+            let file = std::fs::canonicalize(file).unwrap_or_else(|_| file.clone());
+            file.to_string_lossy().into_owned()
         });
-        let mut expander = Expander::new(self.root_name.as_deref(), self.schemafy_path, &schema);
-        expander.expand(&schema)
+        Ok(quote! {
+            #( const _: &[u8] = include_bytes!(#files); )*
+            #tokens
+        })
+    }
+
+    /// Generates the types as formatted source code.
+    pub fn try_generate_code(&self) -> Result<String, Error> {
+        let tokens = self.try_generate()?;
+        self.format(&tokens)
+    }
+
+    /// Formats generated code, in-process and then with `rustfmt` if it is set.
+    pub(crate) fn format(&self, tokens: &proc_macro2::TokenStream) -> Result<String, Error> {
+        let code = format::pretty_print(tokens).map_err(|err| Error::Format { source: err })?;
+        if self.rustfmt {
+            format::rustfmt(&code).map_err(|err| Error::Rustfmt { source: err })
+        } else {
+            Ok(code)
+        }
     }
 
     pub fn generate_to_file<P: ?Sized + AsRef<Path>>(&self, output_file: &'b P) -> io::Result<()> {
-        use std::process::Command;
         let tokens = self.generate();
-        let out = tokens.to_string();
-        std::fs::write(output_file, &out)?;
-        Command::new("rustfmt")
-            .arg(output_file.as_ref().as_os_str())
-            .output()?;
-        Ok(())
+        let code = self.format(&tokens).map_err(io::Error::other)?;
+        std::fs::write(output_file, code)
     }
 }
 
+/// Changes to the derives and attributes of generated types.
+///
+/// The derives are applied in order: derives of every type are removed,
+/// then added, and then the same for the derives of a single type.
+/// Removing both `Serialize` and `Deserialize` leaves the `#[serde]`
+/// attributes of the type without a derive to use them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeOptions {
+    /// Derives added, such as `Eq` or `schemars::JsonSchema`.
+    pub derives: Vec<String>,
+    /// Derives removed, such as `Debug`.
+    pub removed_derives: Vec<String>,
+    /// Attributes added, such as `#[non_exhaustive]`.
+    pub attributes: Vec<String>,
+}
+
 #[derive(Debug, PartialEq)]
 #[must_use]
 pub struct GeneratorBuilder<'a, 'b> {
@@ -78,7 +250,22 @@ impl<'a, 'b> Default for GeneratorBuilder<'a, 'b> {
             inner: Generator {
                 root_name: None,
                 schemafy_path: "::schemafy_core::",
+                serde_path: None,
                 input_file: Path::new("schema.json"),
+                draft: None,
+                registry: Registry::default(),
+                track_dependencies: false,
+                type_options: TypeOptions::default(),
+                named_type_options: BTreeMap::new(),
+                visibility: "pub".into(),
+                field_visibility: None,
+                map_type: "::std::collections::BTreeMap".into(),
+                formats: BTreeMap::new(),
+                format_crates: BTreeSet::new(),
+                strict: false,
+                narrow_integers: false,
+                number_type: NumberType::F64,
+                rustfmt: false,
             },
         }
     }
@@ -101,12 +288,153 @@ impl<'a, 'b> GeneratorBuilder<'a, 'b> {
         self.inner.schemafy_path = schemafy_path;
         self
     }
+    /// Use the `serde` crate at `serde_path`, such as `::serde`, in the
+    /// generated code.
+    pub fn with_serde_path(mut self, serde_path: &'a str) -> Self {
+        self.inner.serde_path = Some(serde_path);
+        self
+    }
+    /// Treat the schema as written in `draft`, regardless of its `$schema` keyword.
+    pub fn with_draft(mut self, draft: Draft) -> Self {
+        self.inner.draft = Some(draft);
+        self
+    }
+    /// Resolve `$ref`s to absolute URIs through `registry`.
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.inner.registry = registry;
+        self
+    }
+    /// Make the generated code depend on the schema files it reads.
+    pub fn with_dependency_tracking(mut self) -> Self {
+        self.inner.track_dependencies = true;
+        self
+    }
+    /// Add `derive`, such as `Eq` or `schemars::JsonSchema`, to the
+    /// derives of every generated type.
+    pub fn with_derive(mut self, derive: impl Into<String>) -> Self {
+        self.inner.type_options.derives.push(derive.into());
+        self
+    }
+    /// Remove `derive`, such as `Debug`, from the derives of every
+    /// generated type.
+    pub fn without_derive(mut self, derive: impl Into<String>) -> Self {
+        self.inner.type_options.removed_derives.push(derive.into());
+        self
+    }
+    /// Add `attribute`, such as `#[non_exhaustive]`, to every generated
+    /// type.
+    pub fn with_attribute(mut self, attribute: impl Into<String>) -> Self {
+        self.inner.type_options.attributes.push(attribute.into());
+        self
+    }
+    /// Add `derive` to the derives of the generated type `name`.
+    pub fn with_type_derive(mut self, name: impl Into<String>, derive: impl Into<String>) -> Self {
+        self.type_options(name).derives.push(derive.into());
+        self
+    }
+    /// Remove `derive` from the derives of the generated type `name`.
+    pub fn without_type_derive(
+        mut self,
+        name: impl Into<String>,
+        derive: impl Into<String>,
+    ) -> Self {
+        self.type_options(name).removed_derives.push(derive.into());
+        self
+    }
+    /// Add `attribute` to the generated type `name`.
+    pub fn with_type_attribute(
+        mut self,
+        name: impl Into<String>,
+        attribute: impl Into<String>,
+    ) -> Self {
+        self.type_options(name).attributes.push(attribute.into());
+        self
+    }
+    fn type_options(&mut self, name: impl Into<String>) -> &mut TypeOptions {
+        self.inner
+            .named_type_options
+            .entry(name.into())
+            .or_default()
+    }
+    /// Use `visibility`, such as `pub(crate)`, for the generated types and
+    /// their fields, unless the fields are given their own visibility.
+    pub fn with_visibility(mut self, visibility: impl Into<String>) -> Self {
+        self.inner.visibility = visibility.into();
+        self
+    }
+    /// Use `visibility` for the fields of the generated structs instead of
+    /// the visibility of the types. An empty string, or `pub(self)`, makes
+    /// the fields private so the types can only be used through methods
+    /// defined next to them.
+    pub fn with_field_visibility(mut self, visibility: impl Into<String>) -> Self {
+        self.inner.field_visibility = Some(visibility.into());
+        self
+    }
+    /// Use `map_type`, such as `::indexmap::IndexMap`, for objects without
+    /// fixed properties instead of `BTreeMap`.
+    pub fn with_map_type(mut self, map_type: impl Into<String>) -> Self {
+        self.inner.map_type = map_type.into();
+        self
+    }
+    /// Use `typ`, such as `::my_crate::Email`, for strings with `format`.
+    ///
+    /// This takes precedence over the types of `with_format_crate`.
+    pub fn with_format(mut self, format: impl Into<String>, typ: impl Into<String>) -> Self {
+        self.inner.formats.insert(format.into(), typ.into());
+        self
+    }
+    /// Generate the strings with the formats `format_crate` has types for
+    /// as those types, `date-time` as `chrono::DateTime` with
+    /// `FormatCrate::Chrono` for instance.
+    ///
+    /// The types are used through `schemafy_core`, which needs the feature
+    /// with the name of the crate. `FormatCrate::Chrono` and
+    /// `FormatCrate::Time` can not be used together.
+    pub fn with_format_crate(mut self, format_crate: FormatCrate) -> Self {
+        self.inner.format_crates.insert(format_crate);
+        self
+    }
+    /// Reject unknown fields in every struct unless the schema allows them
+    /// through `additionalProperties`, `unevaluatedProperties` or
+    /// `patternProperties`.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.inner.strict = strict;
+        self
+    }
+    /// Generate integers as `u8`, `i32`, `u64` and so on, from the bounds
+    /// (`minimum`, `maximum`) and `format` (`int32`, `uint64`, ...) of their
+    /// schemas, instead of generating every integer as an `i64`.
+    pub fn with_narrow_integers(mut self, narrow_integers: bool) -> Self {
+        self.inner.narrow_integers = narrow_integers;
+        self
+    }
+    /// Generate numbers as `number_type` instead of `f64`.
+    /// `schemafy_core::Decimal` keeps amounts of money exactly, but the
+    /// generated code needs the `decimal` feature of `schemafy_core`, which
+    /// enables the `arbitrary_precision` feature of `serde_json` for every
+    /// crate in the build. `serde_json::Number` keeps integers up to 64 bits
+    /// exactly, and other numbers only with `arbitrary_precision`.
+    ///
+    /// Numbers with the format `decimal` are generated as a `Decimal` with
+    /// `NumberType::Decimal`, or else as a `serde_json::Number`.
+    pub fn with_number_type(mut self, number_type: NumberType) -> Self {
+        self.inner.number_type = number_type;
+        self
+    }
+    /// Format the generated code with the `rustfmt` in `PATH` after
+    /// formatting it in-process, which follows the project's
+    /// `rustfmt.toml` but needs rustfmt to be installed.
+    pub fn with_rustfmt(mut self, rustfmt: bool) -> Self {
+        self.inner.rustfmt = rustfmt;
+        self
+    }
     pub fn build(self) -> Generator<'a, 'b> {
         self.inner
     }
 }
 
-fn get_crate_root() -> std::io::Result<PathBuf> {
+#[allow(clippy::useless_conversion)]
+pub(crate) fn get_crate_root() -> std::io::Result<PathBuf> {
     if let Ok(path) = std::env::var("CARGO_MANIFEST_DIR") {
         return Ok(PathBuf::from(path));
     }
@@ -0,0 +1,183 @@
+//! The narrowest Rust integer type for the values an integer schema allows.
+
+use serde_json::{Number, Value};
+
+use crate::Schema;
+
+// The largest integer of each type
+const UNSIGNED: [(&str, i128); 4] = [
+    ("u8", u8::MAX as i128),
+    ("u16", u16::MAX as i128),
+    ("u32", u32::MAX as i128),
+    ("u64", u64::MAX as i128),
+];
+
+// The smallest and largest integers of each type
+const SIGNED: [(&str, i128, i128); 4] = [
+    ("i8", i8::MIN as i128, i8::MAX as i128),
+    ("i16", i16::MIN as i128, i16::MAX as i128),
+    ("i32", i32::MIN as i128, i32::MAX as i128),
+    ("i64", i64::MIN as i128, i64::MAX as i128),
+];
+
+/// The type of the integers `schema` allows, from its `format` (such as `int32` or `uint64`)
+/// or else its bounds.
+///
+/// Integers without bounds are `i64`, and so are those without a lower bound unless the upper
+/// bound does not fit. Integers which can not be negative are unsigned, `u64` unless they have an
+/// upper bound.
+pub(crate) fn integer_type(schema: &Schema) -> &'static str {
+    if let Some(typ) = schema.format.as_deref().and_then(format_type) {
+        return typ;
+    }
+
+    let minimum = bound(
+        schema.minimum.as_ref(),
+        schema.exclusive_minimum.as_ref(),
+        Ord::max,
+        |minimum, exclusive| {
+            if exclusive {
+                minimum.floor() + 1.0
+            } else {
+                minimum.ceil()
+            }
+        },
+    );
+    let maximum = bound(
+        schema.maximum.as_ref(),
+        schema.exclusive_maximum.as_ref(),
+        Ord::min,
+        |maximum, exclusive| {
+            if exclusive {
+                maximum.ceil() - 1.0
+            } else {
+                maximum.floor()
+            }
+        },
+    );
+
+    match (minimum, maximum) {
+        (Some(minimum), maximum) if minimum >= 0 => match maximum {
+            Some(maximum) => UNSIGNED
+                .iter()
+                .find(|(_, max)| maximum <= *max)
+                .map_or("u128", |(typ, _)| typ),
+            None => "u64",
+        },
+        (Some(minimum), maximum) => {
+            // Without an upper bound the integer may be as large as an `i64`
+            let maximum = maximum.unwrap_or(i64::MAX.into());
+            SIGNED
+                .iter()
+                .find(|(_, min, max)| minimum >= *min && maximum <= *max)
+                .map_or("i128", |(typ, _, _)| typ)
+        }
+        (None, Some(maximum)) if maximum > i64::MAX.into() => "i128",
+        (None, _) => "i64",
+    }
+}
+
+/// The type of integers with `format`, if it names one.
+fn format_type(format: &str) -> Option<&'static str> {
+    Some(match format {
+        "int8" => "i8",
+        "int16" => "i16",
+        "int32" => "i32",
+        "int64" => "i64",
+        "int128" => "i128",
+        "uint8" => "u8",
+        "uint16" => "u16",
+        "uint32" => "u32",
+        "uint64" => "u64",
+        "uint128" => "u128",
+        _ => return None,
+    })
+}
+
+/// The inclusive integer bound from `bound` and `exclusive`, which is either a flag on `bound`
+/// (draft 4) or a bound of its own (draft 6 and later). `tighter` picks the tighter of two bounds
+/// and `fractional` rounds a fractional bound to an inclusive integer.
+fn bound(
+    bound: Option<&Number>,
+    exclusive: Option<&Value>,
+    tighter: fn(i128, i128) -> i128,
+    fractional: fn(f64, bool) -> f64,
+) -> Option<i128> {
+    let step = if tighter(0, 1) == 1 { 1 } else { -1 };
+    let integer = |bound: &Number, exclusive: bool| {
+        let exact = bound
+            .as_u64()
+            .map(i128::from)
+            .or_else(|| bound.as_i64().map(i128::from));
+        match exact {
+            Some(bound) if exclusive => Some(bound + step),
+            Some(bound) => Some(bound),
+            // Saturates where the bound does not fit an `i128`
+            None => bound
+                .as_f64()
+                .map(|bound| fractional(bound, exclusive) as i128),
+        }
+    };
+    let inclusive = bound.and_then(|bound| integer(bound, exclusive == Some(&Value::Bool(true))));
+    let exclusive = match exclusive {
+        Some(Value::Number(bound)) => integer(bound, true),
+        _ => None,
+    };
+    match (inclusive, exclusive) {
+        (Some(inclusive), Some(exclusive)) => Some(tighter(inclusive, exclusive)),
+        (inclusive, exclusive) => inclusive.or(exclusive),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typ(schema: &str) -> &'static str {
+        integer_type(&serde_json::from_str(schema).unwrap())
+    }
+
+    #[test]
+    fn integer_types() {
+        assert_eq!(typ(r#"{}"#), "i64");
+        assert_eq!(typ(r#"{ "minimum": 0, "maximum": 255 }"#), "u8");
+        assert_eq!(typ(r#"{ "minimum": 0, "maximum": 256 }"#), "u16");
+        assert_eq!(typ(r#"{ "minimum": 0, "exclusiveMaximum": 256 }"#), "u8");
+        assert_eq!(
+            typ(r#"{ "minimum": -1, "exclusiveMinimum": true, "maximum": 255 }"#),
+            "u8"
+        );
+        assert_eq!(typ(r#"{ "minimum": 1 }"#), "u64");
+        assert_eq!(typ(r#"{ "minimum": 0, "maximum": 1e20 }"#), "u128");
+        assert_eq!(typ(r#"{ "minimum": -128, "maximum": 127 }"#), "i8");
+        assert_eq!(typ(r#"{ "minimum": -129, "maximum": 127 }"#), "i16");
+        assert_eq!(typ(r#"{ "minimum": -1 }"#), "i64");
+        assert_eq!(typ(r#"{ "minimum": -1e20 }"#), "i128");
+        assert_eq!(typ(r#"{ "maximum": 10 }"#), "i64");
+        assert_eq!(typ(r#"{ "maximum": 1e20 }"#), "i128");
+        assert_eq!(typ(r#"{ "minimum": 0, "maximum": 4294967295 }"#), "u32");
+        assert_eq!(
+            typ(r#"{ "minimum": 0, "maximum": 18446744073709551615 }"#),
+            "u64"
+        );
+        assert_eq!(
+            typ(r#"{ "minimum": 0, "maximum": 18446744073709551616 }"#),
+            "u128"
+        );
+        assert_eq!(
+            typ(r#"{ "minimum": 0, "exclusiveMaximum": 18446744073709551615 }"#),
+            "u64"
+        );
+        assert_eq!(typ(r#"{ "minimum": -9223372036854775808 }"#), "i64");
+        assert_eq!(typ(r#"{ "maximum": 9223372036854775807 }"#), "i64");
+        assert_eq!(typ(r#"{ "maximum": 9223372036854775808 }"#), "i128");
+        assert_eq!(
+            typ(r#"{ "minimum": -9223372036854775808, "maximum": 9223372036854775807 }"#),
+            "i64"
+        );
+        assert_eq!(typ(r#"{ "minimum": 0.5, "maximum": 255.5 }"#), "u8");
+        assert_eq!(typ(r#"{ "exclusiveMinimum": -0.5, "maximum": 255 }"#), "u8");
+        assert_eq!(typ(r#"{ "format": "int32", "minimum": 0 }"#), "i32");
+        assert_eq!(typ(r#"{ "format": "uint64" }"#), "u64");
+    }
+}
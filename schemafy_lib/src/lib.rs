@@ -4,7 +4,7 @@
 // #![doc(include = "../README.md")]
 
 //! This is a Rust crate which can take a [json schema (draft
-//! 4)](http://json-schema.org/) and generate Rust types which are
+//! 4, 6, 7, 2019-09 or 2020-12)](http://json-schema.org/) and generate Rust types which are
 //! serializable with [serde](https://serde.rs/). No checking such as
 //! `min_value` are done but instead only the structure of the schema
 //! is followed as closely as possible.
@@ -27,11 +27,6 @@
 //! A proc macro is available in [`schemafy`](https://docs.rs/schemafy) crate
 //!
 //! ```rust
-//! extern crate serde;
-//! extern crate schemafy_core;
-//! extern crate serde_json;
-//!
-//! use serde::{Serialize, Deserialize};
 //! use schemafy_lib::Expander;
 //!
 //! let json = std::fs::read_to_string("src/schema.json").expect("Read schema JSON file");
@@ -46,20 +41,37 @@
 //! let code = expander.expand(&schema);
 //! ```
 
-#[macro_use]
-extern crate serde_derive;
-
 #[macro_use]
 extern crate quote;
 
+pub mod build;
+mod comparisons;
+mod documents;
+mod draft;
+mod enums;
+mod error;
+mod format;
+mod formats;
 pub mod generator;
+mod integers;
+mod numbers;
+mod registry;
 
-/// Types from the JSON Schema meta-schema (draft 4).
+/// Types from the JSON Schema meta-schema (draft 4, extended with the keywords of later drafts).
 ///
 /// This module is itself generated from a JSON schema.
 mod schema;
 
-use std::{borrow::Cow, convert::TryFrom};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    path::Path,
+};
+
+use documents::{percent_decode, Document, Documents};
+use draft::walk_subschemas_mut;
 
 use inflector::Inflector;
 
@@ -67,9 +79,14 @@ use serde_json::Value;
 
 use uriparse::{Fragment, URI};
 
+pub use draft::{Draft, UnsupportedDialect};
+pub use error::{Error, Location};
+pub use registry::Registry;
 pub use schema::{Schema, SimpleTypes};
 
-pub use generator::{Generator, GeneratorBuilder};
+pub use formats::FormatCrate;
+pub use generator::{Generator, GeneratorBuilder, TypeOptions};
+pub use numbers::NumberType;
 
 use proc_macro2::{Span, TokenStream};
 
@@ -132,35 +149,16 @@ pub fn str_to_ident(s: &str) -> syn::Ident {
     syn::Ident::new(&s, Span::call_site())
 }
 
-fn rename_keyword(prefix: &str, s: &str) -> Option<TokenStream> {
+/// Returns the identifier used for the field `s` and whether it differs from `s`.
+#[allow(clippy::manual_pattern_char_comparison)]
+fn field_ident(s: &str) -> (syn::Ident, bool) {
     let n = str_to_ident(s);
-
-    if n == s {
-        return None;
-    }
-
-    if prefix.is_empty() {
-        Some(quote! {
-            #[serde(rename = #s)]
-            #n
-        })
-    } else {
-        let prefix = syn::Ident::new(prefix, Span::call_site());
-        Some(quote! {
-            #[serde(rename = #s)]
-            #prefix #n
-        })
-    }
-}
-
-fn field(s: &str) -> TokenStream {
-    if let Some(t) = rename_keyword("pub", s) {
-        return t;
+    if n != s {
+        return (n, true);
     }
     let snake = s.to_snake_case();
     if snake == s && !snake.contains(|c: char| c == '$' || c == '#') {
-        let field = syn::Ident::new(s, Span::call_site());
-        return quote!( pub #field );
+        return (syn::Ident::new(s, Span::call_site()), false);
     }
 
     let field = if snake.is_empty() {
@@ -168,13 +166,48 @@ fn field(s: &str) -> TokenStream {
     } else {
         str_to_ident(&snake)
     };
+    (field, true)
+}
+
+/// Picks the field identifiers for all `properties` of an object.
+///
+/// Properties which can be used as identifiers as-is keep their name, renamed properties
+/// which would collide with another field (`$id` and `id` for instance) get an `_` appended.
+fn field_idents<'s>(names: impl Iterator<Item = &'s String> + Clone) -> Vec<(syn::Ident, bool)> {
+    let mut used = names
+        .clone()
+        .map(|name| field_ident(name))
+        .filter(|(_, renamed)| !renamed)
+        .map(|(ident, _)| ident.to_string())
+        .collect::<std::collections::BTreeSet<_>>();
+    names
+        .map(|name| {
+            let (ident, renamed) = field_ident(name);
+            if !renamed {
+                return (ident, renamed);
+            }
+            let mut ident = ident.to_string();
+            while used.contains(&ident) {
+                ident.push('_');
+            }
+            used.insert(ident.clone());
+            (syn::Ident::new(&ident, Span::call_site()), renamed)
+        })
+        .collect()
+}
 
-    quote! {
-        #[serde(rename = #s)]
-        pub #field
+fn field((ident, renamed): &(syn::Ident, bool), s: &str, visibility: &TokenStream) -> TokenStream {
+    if *renamed {
+        quote! {
+            #[serde(rename = #s)]
+            #visibility #ident
+        }
+    } else {
+        quote!( #visibility #ident )
     }
 }
 
+#[allow(clippy::needless_borrowed_reference)]
 fn merge_option<T, F>(mut result: &mut Option<T>, r: &Option<T>, f: F)
 where
     F: FnOnce(&mut T, &T),
@@ -207,15 +240,279 @@ fn merge_all_of(result: &mut Schema, r: &Schema) {
         result.description = Some(description.clone());
     }
 
+    if let Some(ref additional_properties) = r.additional_properties {
+        result.additional_properties = Some(additional_properties.clone());
+    }
+
+    if let Some(ref unevaluated_properties) = r.unevaluated_properties {
+        result.unevaluated_properties = Some(unevaluated_properties.clone());
+    }
+
+    if let Some(ref const_) = r.const_ {
+        result.const_ = Some(const_.clone());
+    }
+
+    if let Some(ref enum_) = r.enum_ {
+        result.enum_ = Some(enum_.clone());
+    }
+
     merge_option(&mut result.required, &r.required, |required, r_required| {
         required.extend(r_required.iter().cloned());
     });
 
-    result.type_.retain(|e| r.type_.contains(e));
+    if !r.type_.is_empty() {
+        result.type_.retain(|e| r.type_.contains(e));
+    }
+}
+
+/// Whether unknown properties are rejected, through `additionalProperties: false` or
+/// `unevaluatedProperties: false`.
+fn denies_unknown_properties(schema: &Schema) -> bool {
+    schema.additional_properties == Some(Value::Bool(false))
+        || schema.unevaluated_properties == Some(Value::Bool(false))
+}
+
+/// Whether a schema with a `$ref` has other keywords which constrain the value further.
+///
+/// Annotations such as `description` do not change the generated type so they are ignored.
+fn has_ref_siblings(schema: &Schema) -> bool {
+    schema.ref_.is_some()
+        && (!schema.type_.is_empty()
+            || !schema.properties.is_empty()
+            || schema.required.is_some()
+            || !schema.items.is_empty()
+            || schema.additional_properties.is_some()
+            || schema.unevaluated_properties.is_some()
+            || schema.all_of.is_some()
+            || schema.any_of.is_some()
+            || schema.one_of.is_some()
+            || schema.enum_.is_some()
+            || schema.const_.is_some()
+            || schema.if_.is_some())
+}
+
+/// Records the `$anchor`s of `schema`, in the document `document`, and of its (nested)
+/// definitions.
+///
+/// Anchors map to the name of the definition they are declared in, `None` for the root of the
+/// document.
+fn collect_anchors<'r>(
+    document: &str,
+    name: Option<&str>,
+    schema: &'r Schema,
+    anchors: &mut BTreeMap<String, (Option<String>, &'r Schema)>,
+) {
+    if let Some(ref anchor) = schema.anchor {
+        anchors.insert(
+            format!("{}#{}", document, anchor),
+            (name.map(|s| s.to_owned()), schema),
+        );
+    }
+    for (name, def) in schema.definitions.iter().chain(&schema.defs) {
+        collect_anchors(document, Some(name), def, anchors);
+    }
+}
+
+/// Whether no value is valid against the schema, as is the case for the boolean schema `false`
+/// (which is read as `{"not": {}}`).
+fn is_false_schema(schema: &Schema) -> bool {
+    schema.not.as_deref() == Some(&empty_schema())
+}
+
+/// The schema `{}`, which any value is valid against.
+fn empty_schema() -> Schema {
+    serde_json::from_value(Value::Object(Default::default())).unwrap()
+}
+
+/// Whether the schema uses `if` together with `then` and/or `else`.
+fn is_conditional(schema: &Schema) -> bool {
+    schema.if_.is_some() && (schema.then.is_some() || schema.else_.is_some())
+}
+
+/// The documentation for a schema, its `description` followed by its `$comment`.
+fn doc_text(schema: &Schema) -> Option<String> {
+    match (&schema.description, &schema.comment) {
+        (Some(description), Some(comment)) => Some(format!("{}\n\n{}", description, comment)),
+        (Some(text), None) | (None, Some(text)) => Some(text.clone()),
+        (None, None) => None,
+    }
+}
+
+/// Returns the fragment of the URI reference `s`, without the leading `#`.
+fn ref_fragment(s: &str) -> String {
+    // ref is supposed to be be a valid URI, however we should better have a fallback plan
+    URI::try_from(s)
+        .map(|uri| uri.fragment().map(Fragment::to_owned))
+        .ok()
+        .flatten()
+        .or({
+            let s = s.strip_prefix('#').unwrap_or(s);
+            Fragment::try_from(s).ok()
+        })
+        .map(|fragment| fragment.to_string())
+        .unwrap_or_else(|| {
+            s.split_once('#')
+                .map_or(s, |(_, fragment)| fragment)
+                .to_owned()
+        })
+}
+
+/// The values allowed by `enum`, or the single value allowed by `const`.
+fn enum_values(schema: &Schema) -> &[Value] {
+    match (&schema.enum_, &schema.const_) {
+        (Some(values), _) => values,
+        (None, Some(value)) => std::slice::from_ref(value),
+        (None, None) => &[],
+    }
+}
+
+/// Whether `schema` is an `enum` of strings, numbers and booleans, which can become a Rust enum.
+fn is_scalar_enum(schema: &Schema) -> bool {
+    schema.enum_.as_ref().is_some_and(|values| {
+        values.iter().any(|value| !value.is_null())
+            && values
+                .iter()
+                .all(|value| !value.is_array() && !value.is_object())
+    })
+}
+
+/// Splits the JSON pointer (RFC 6901) in a (percent-encoded) URI fragment into its reference
+/// tokens.
+///
+/// Returns `None` if the fragment is not a JSON pointer.
+fn pointer_tokens(fragment: &str) -> Option<Vec<String>> {
+    let pointer = percent_decode(fragment);
+    let pointer = pointer.strip_prefix('/')?;
+    Some(
+        pointer
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Whether the JSON pointer `tokens` points to a (nested) definition, which is generated as a
+/// type named after the definition.
+fn is_definition_pointer(tokens: &[String]) -> bool {
+    !tokens.is_empty()
+        && tokens
+            .chunks(2)
+            .all(|pair| pair.len() == 2 && (pair[0] == "definitions" || pair[0] == "$defs"))
+}
+
+/// The key identifying the subschema at the JSON pointer `tokens` in `document`.
+///
+/// Arrays only use the first schema of a (draft 4) array of `items`, so `items/0` is the same
+/// subschema as `items`.
+fn pointer_key(document: &str, tokens: &[String]) -> String {
+    let mut key = format!("{}#", document);
+    let mut previous = None;
+    for token in tokens {
+        if previous == Some("items") && token == "0" {
+            continue;
+        }
+        key.push('/');
+        key.push_str(&token.replace('~', "~0").replace('/', "~1"));
+        previous = Some(token.as_str());
+    }
+    key
+}
+
+/// The name of the type generated for the definition (or document) `key`.
+fn type_name(key: &str) -> String {
+    replace_numeric_start(&replace_invalid_identifier_chars(&key.to_pascal_case()))
+}
+
+/// The keys of the definitions of `schema` and of the definitions nested in them, with the JSON
+/// pointers to them from `pointer`.
+fn definition_keys(schema: &Schema, pointer: &str, keys: &mut Vec<(String, String)>) {
+    let definitions = schema.definitions.iter().map(|def| ("definitions", def));
+    let defs = schema.defs.iter().map(|def| ("$defs", def));
+    for (keyword, (key, def)) in definitions.chain(defs) {
+        let pointer = format!(
+            "{}/{}/{}",
+            pointer,
+            keyword,
+            key.replace('~', "~0").replace('/', "~1")
+        );
+        keys.push((key.clone(), pointer.clone()));
+        definition_keys(def, &pointer, keys);
+    }
+}
+
+/// The names to try for the definition (or document) `key` of the document `uri`, in order:
+/// `key` itself and then `key` prefixed with more and more of the path of `uri`, starting with
+/// the file name unless `key` is the name of the document itself.
+fn qualified_keys(uri: &str, key: &str, is_document: bool) -> Vec<String> {
+    let path = uri.split(['#', '?']).next().unwrap_or("");
+    let segments = path
+        .rsplit('/')
+        .filter(|segment| !segment.is_empty())
+        .enumerate()
+        .map(|(i, segment)| match i {
+            0 => segment.split('.').next().unwrap_or(segment),
+            _ => segment,
+        })
+        .skip(if is_document { 1 } else { 0 });
+    let mut keys = vec![key.to_owned()];
+    for segment in segments {
+        let qualified = format!("{}_{}", segment, keys[keys.len() - 1]);
+        keys.push(qualified);
+    }
+    keys
+}
+
+/// The definitions of `schema`, which are expanded even if `schema` itself is not.
+fn definition_schemas(schema: &Schema) -> Vec<&Schema> {
+    schema
+        .definitions
+        .values()
+        .chain(schema.defs.values())
+        .collect()
+}
+
+/// Follows the JSON pointer `tokens` through the subschemas of `schema`.
+///
+/// Returns `None` if the pointer does not point to a subschema stored as a `Schema`, such as
+/// the values of `additionalProperties` or of unknown keywords, which are stored as a `Value`.
+fn walk_pointer<'s>(mut schema: &'s Schema, tokens: &[String]) -> Option<&'s Schema> {
+    let mut tokens = tokens.iter().map(|token| token.as_str()).peekable();
+    let index = |token: Option<&str>| token?.parse::<usize>().ok();
+    while let Some(token) = tokens.next() {
+        schema = match token {
+            "definitions" => schema.definitions.get(tokens.next()?)?,
+            "$defs" => schema.defs.get(tokens.next()?)?,
+            "properties" => schema.properties.get(tokens.next()?)?,
+            "patternProperties" => schema.pattern_properties.get(tokens.next()?)?,
+            "dependentSchemas" => schema.dependent_schemas.get(tokens.next()?)?,
+            "allOf" => schema.all_of.as_ref()?.get(index(tokens.next())?)?,
+            "anyOf" => schema.any_of.as_ref()?.get(index(tokens.next())?)?,
+            "oneOf" => schema.one_of.as_ref()?.get(index(tokens.next())?)?,
+            "prefixItems" => schema.prefix_items.as_ref()?.get(index(tokens.next())?)?,
+            // `items` is either a single schema or an array of schemas
+            "items" => match index(tokens.peek().copied()) {
+                Some(i) => {
+                    tokens.next();
+                    schema.items.get(i)?
+                }
+                None => schema.items.first()?,
+            },
+            "contains" => schema.contains.as_deref()?,
+            "not" => schema.not.as_deref()?,
+            "if" => schema.if_.as_deref()?,
+            "then" => schema.then.as_deref()?,
+            "else" => schema.else_.as_deref()?,
+            "propertyNames" => schema.property_names.as_deref()?,
+            _ => return None,
+        };
+    }
+    Some(schema)
 }
 
 const LINE_LENGTH: usize = 100;
 const INDENT_LENGTH: usize = 4;
+/// The longest tuple `TupleRest` (and the standard library traits) are implemented for.
+const MAX_PREFIX_ITEMS: usize = 12;
 
 fn make_doc_comment(mut comment: &str, remaining_line: usize) -> TokenStream {
     let mut out_comment = String::new();
@@ -262,22 +559,31 @@ struct FieldExpander<'a, 'r: 'a> {
 impl<'a, 'r> FieldExpander<'a, 'r> {
     fn expand_fields(&mut self, type_name: &str, schema: &Schema) -> Vec<TokenStream> {
         let schema = self.expander.schema(schema);
+        let idents = field_idents(schema.properties.keys());
         schema
             .properties
             .iter()
-            .map(|(field_name, value)| {
+            .zip(&idents)
+            .map(|((field_name, value), ident)| {
                 self.expander.current_field.clone_from(field_name);
-                let key = field(field_name);
+                let visibility = self
+                    .expander
+                    .field_visibility
+                    .as_ref()
+                    .unwrap_or(&self.expander.visibility);
+                let key = field(ident, field_name, visibility);
                 let required = schema
                     .required
                     .iter()
                     .flat_map(|a| a.iter())
                     .any(|req| req == field_name);
-                let field_type = self.expander.expand_type(type_name, required, value);
+                let field_type = self.expander.at(&["properties", field_name], |expander| {
+                    expander.expand_type(type_name, required, value)
+                });
                 if !field_type.typ.starts_with("Option<") {
                     self.default = false;
                 }
-                let typ = field_type.typ.parse::<TokenStream>().unwrap();
+                let typ = self.expander.parse_code::<syn::Type>(&field_type.typ);
 
                 let default = if field_type.default {
                     Some(quote! { #[serde(default)] })
@@ -290,13 +596,12 @@ impl<'a, 'r> FieldExpander<'a, 'r> {
                     let attributes = field_type
                         .attributes
                         .iter()
-                        .map(|attr| attr.parse::<TokenStream>().unwrap());
+                        .map(|attr| self.expander.parse_code::<syn::Meta>(attr));
                     Some(quote! {
                         #[serde( #(#attributes),* )]
                     })
                 };
-                let comment = value
-                    .description
+                let comment = doc_text(value)
                     .as_ref()
                     .map(|comment| make_doc_comment(comment, LINE_LENGTH - INDENT_LENGTH));
                 quote! {
@@ -313,10 +618,53 @@ impl<'a, 'r> FieldExpander<'a, 'r> {
 pub struct Expander<'r> {
     root_name: Option<&'r str>,
     schemafy_path: &'r str,
+    /// The path to the `serde` crate, `None` for the one re-exported by `schemafy_core`.
+    serde_path: Option<&'r str>,
     root: &'r Schema,
+    draft: Draft,
+    /// Changes to the derives and attributes of every generated type.
+    type_options: TypeOptions,
+    /// Changes to the derives and attributes of single types, keyed by their name.
+    named_type_options: BTreeMap<String, TypeOptions>,
+    /// The visibility of the generated types, and of their fields unless `field_visibility` is
+    /// set.
+    visibility: TokenStream,
+    field_visibility: Option<TokenStream>,
+    /// The map type used for objects without fixed properties.
+    map_type: &'r str,
+    /// The types of strings with a `format`, taking precedence over `format_crates`.
+    formats: BTreeMap<String, String>,
+    /// The crates whose types strings with a `format` are generated as.
+    format_crates: BTreeSet<FormatCrate>,
+    /// Whether structs reject unknown fields unless the schema allows them explicitly.
+    strict: bool,
+    /// Whether integers get the narrowest type which fits their bounds, rather than `i64`.
+    narrow_integers: bool,
+    /// The type of numbers without a `format` which says otherwise.
+    number_type: NumberType,
+    /// The file the root schema was read from, if any.
+    root_file: Option<&'r Path>,
+    /// The root schema as read, if available.
+    root_value: Option<&'r Value>,
+    /// Other documents referenced by the root schema, keyed by their URI.
+    documents: BTreeMap<&'r str, &'r Document>,
+    /// The keys the types of the documents other than the root document (keyed by their URI and
+    /// `None`) and of their definitions (keyed by their URI and the key of the definition) are
+    /// named after, which are qualified where they would clash with other types.
+    document_names: BTreeMap<(String, Option<String>), String>,
+    /// The references to subschemas by a JSON pointer (other than definitions) and the names
+    /// of the types generated for them, keyed by `pointer_key`.
+    pointer_types: RefCell<BTreeMap<String, (String, String)>>,
+    anchors: BTreeMap<String, (Option<String>, &'r Schema)>,
+    /// The document being expanded, `""` for the root document.
+    current_document: &'r str,
+    /// The JSON pointer to the subschema being expanded, within `current_document`.
+    current_pointer: String,
     current_type: String,
     current_field: String,
     types: Vec<(String, TokenStream)>,
+    /// The first error found while expanding.
+    error: RefCell<Option<Error>>,
 }
 
 struct FieldType {
@@ -344,33 +692,333 @@ impl<'r> Expander<'r> {
         schemafy_path: &'r str,
         root: &'r Schema,
     ) -> Expander<'r> {
+        let draft = root
+            .schema
+            .as_deref()
+            .and_then(Draft::from_uri)
+            .unwrap_or_default();
+        let mut anchors = BTreeMap::new();
+        collect_anchors("", None, root, &mut anchors);
         Expander {
             root_name,
             root,
             schemafy_path,
+            serde_path: None,
+            draft,
+            type_options: TypeOptions::default(),
+            named_type_options: BTreeMap::new(),
+            visibility: quote!(pub),
+            field_visibility: None,
+            map_type: "::std::collections::BTreeMap",
+            formats: BTreeMap::new(),
+            format_crates: BTreeSet::new(),
+            strict: false,
+            narrow_integers: false,
+            number_type: NumberType::F64,
+            root_file: None,
+            root_value: None,
+            documents: BTreeMap::new(),
+            document_names: BTreeMap::new(),
+            pointer_types: RefCell::default(),
+            anchors,
+            current_document: "",
+            current_pointer: String::new(),
             current_field: "".into(),
             current_type: "".into(),
             types: Vec::new(),
+            error: RefCell::default(),
         }
     }
 
-    fn type_ref(&self, s: &str) -> String {
-        // ref is supposed to be be a valid URI, however we should better have a fallback plan
-        let fragment = URI::try_from(s)
-            .map(|uri| uri.fragment().map(Fragment::to_owned))
-            .ok()
-            .flatten()
-            .or({
-                let s = s.strip_prefix('#').unwrap_or(s);
-                Fragment::try_from(s).ok()
-            })
-            .map(|fragment| fragment.to_string())
-            .unwrap_or_else(|| s.to_owned());
+    /// Expand the schema as written in `draft` instead of the draft named by its `$schema`.
+    pub fn with_draft(mut self, draft: Draft) -> Self {
+        self.draft = draft;
+        self
+    }
 
-        let ref_ = if fragment.is_empty() {
-            self.root_name.expect("No root name specified for schema")
-        } else {
-            fragment.split('/').last().expect("Component")
+    /// Use the `serde` crate at `serde_path` (such as `::serde`) instead of the one re-exported
+    /// by `schemafy_core`.
+    pub fn with_serde_path(mut self, serde_path: &'r str) -> Self {
+        if self.check_option::<syn::Path>("serde_path", serde_path) {
+            self.serde_path = Some(serde_path);
+        }
+        self
+    }
+
+    /// Add `derives` (paths such as `Eq` or `schemars::JsonSchema`) to the derives of every
+    /// generated type.
+    pub fn with_derives<I>(mut self, derives: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        for derive in derives {
+            let derive = derive.into();
+            if self.check_option::<syn::Path>("derive", &derive) {
+                self.type_options.derives.push(derive);
+            }
+        }
+        self
+    }
+
+    /// Apply `options` to every generated type, in addition to the derives added by
+    /// `with_derives`.
+    pub fn with_type_options(mut self, options: TypeOptions) -> Self {
+        self.check_type_options(&options);
+        self.type_options.derives.extend(options.derives);
+        self.type_options
+            .removed_derives
+            .extend(options.removed_derives);
+        self.type_options.attributes.extend(options.attributes);
+        self
+    }
+
+    /// Apply `options` to the generated type named `name`, after the options applied to every
+    /// type.
+    pub fn with_named_type_options(
+        mut self,
+        name: impl Into<String>,
+        options: TypeOptions,
+    ) -> Self {
+        self.check_type_options(&options);
+        self.named_type_options.insert(name.into(), options);
+        self
+    }
+
+    /// Use `visibility` (such as `pub(crate)`) for the generated types and their fields instead
+    /// of `pub`.
+    pub fn with_visibility(mut self, visibility: &str) -> Self {
+        if let Some(visibility) = self.parse_option::<syn::Visibility>("visibility", visibility) {
+            self.visibility = visibility;
+        }
+        self
+    }
+
+    /// Use `visibility` (such as `pub(crate)`, or `""` for private fields) for the fields of
+    /// the generated structs instead of the visibility of the types.
+    pub fn with_field_visibility(mut self, visibility: &str) -> Self {
+        self.field_visibility =
+            self.parse_option::<syn::Visibility>("field_visibility", visibility);
+        self
+    }
+
+    /// Use `map_type` (such as `::indexmap::IndexMap`) instead of `BTreeMap` for objects
+    /// without fixed properties.
+    pub fn with_map_type(mut self, map_type: &'r str) -> Self {
+        if self.check_option::<syn::Path>("map_type", map_type) {
+            self.map_type = map_type;
+        }
+        self
+    }
+
+    /// Use `typ` (such as `::my_crate::Email`) for strings with `format`, instead of `String`
+    /// or the type of a `FormatCrate`.
+    pub fn with_format(mut self, format: impl Into<String>, typ: impl Into<String>) -> Self {
+        let typ = typ.into();
+        if self.check_option::<syn::Type>("format", &typ) {
+            self.formats.insert(format.into(), typ);
+        }
+        self
+    }
+
+    /// Generate strings with the `format`s `format_crate` has types for as those types.
+    ///
+    /// `FormatCrate::Chrono` and `FormatCrate::Time` map the same formats, so only one of them
+    /// may be used.
+    pub fn with_format_crate(mut self, format_crate: FormatCrate) -> Self {
+        let other = match format_crate {
+            FormatCrate::Chrono => Some(FormatCrate::Time),
+            FormatCrate::Time => Some(FormatCrate::Chrono),
+            _ => None,
+        };
+        match other.filter(|other| self.format_crates.contains(other)) {
+            Some(other) => self.fail(Error::InvalidOption {
+                option: "format_crate".to_owned(),
+                value: format_crate.to_string(),
+                message: format!(
+                    "`{}` is used already, and maps the same formats as `{}`",
+                    other, format_crate
+                ),
+            }),
+            None => {
+                self.format_crates.insert(format_crate);
+            }
+        }
+        self
+    }
+
+    /// Reject unknown fields in all structs, unless `additionalProperties`,
+    /// `unevaluatedProperties` or `patternProperties` allow them.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Pick the narrowest type (such as `u8`) which fits the bounds and `format` of each integer
+    /// schema, instead of generating every integer as an `i64`.
+    pub fn with_narrow_integers(mut self, narrow_integers: bool) -> Self {
+        self.narrow_integers = narrow_integers;
+        self
+    }
+
+    /// Use `number_type` for `"type": "number"` instead of `f64`. Numbers with the format
+    /// `decimal` are a `Decimal` with `NumberType::Decimal`, or else a `serde_json::Number`.
+    ///
+    /// The code generated with `NumberType::Decimal` only compiles with the `decimal` feature of
+    /// `schemafy_core`, which enables the `arbitrary_precision` feature of `serde_json`.
+    pub fn with_number_type(mut self, number_type: NumberType) -> Self {
+        self.number_type = number_type;
+        self
+    }
+
+    /// Also generate the types of the documents referenced by the root schema.
+    pub(crate) fn with_documents(mut self, documents: &'r Documents) -> Self {
+        self.root_file = documents.root_file.as_deref();
+        self.root_value = Some(&documents.root_value);
+        for (uri, document) in &documents.external {
+            collect_anchors(uri, None, &document.schema, &mut self.anchors);
+            self.documents.insert(uri, document);
+        }
+        self
+    }
+
+    /// Parses the value of the option `option`, recording an error if it is not valid.
+    fn parse_option<T>(&self, option: &str, value: &str) -> Option<TokenStream>
+    where
+        T: syn::parse::Parse + quote::ToTokens,
+    {
+        match syn::parse_str::<T>(value) {
+            Ok(parsed) => Some(parsed.into_token_stream()),
+            Err(err) => {
+                self.fail(Error::InvalidOption {
+                    option: option.to_owned(),
+                    value: value.to_owned(),
+                    message: err.to_string(),
+                });
+                None
+            }
+        }
+    }
+
+    fn check_option<T>(&self, option: &str, value: &str) -> bool
+    where
+        T: syn::parse::Parse + quote::ToTokens,
+    {
+        self.parse_option::<T>(option, value).is_some()
+    }
+
+    fn check_type_options(&self, options: &TypeOptions) {
+        for derive in options.derives.iter().chain(&options.removed_derives) {
+            self.check_option::<syn::Path>("derive", derive);
+        }
+        for attribute in &options.attributes {
+            let parsed = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attribute);
+            if let Err(err) = parsed {
+                self.fail(Error::InvalidOption {
+                    option: "attribute".to_owned(),
+                    value: attribute.clone(),
+                    message: err.to_string(),
+                });
+            }
+        }
+    }
+
+    /// Parses the Rust code `code` generated for the subschema being expanded, recording an
+    /// error if it is not valid (because of an option it is made of).
+    fn parse_code<T>(&self, code: &str) -> TokenStream
+    where
+        T: syn::parse::Parse + quote::ToTokens,
+    {
+        match syn::parse_str::<T>(code) {
+            Ok(parsed) => parsed.into_token_stream(),
+            Err(err) => {
+                self.fail(Error::InvalidCode {
+                    location: self.location(),
+                    code: code.to_owned(),
+                    message: err.to_string(),
+                });
+                TokenStream::new()
+            }
+        }
+    }
+
+    /// The file and JSON pointer of the subschema being expanded.
+    fn location(&self) -> Location {
+        let file = match self.documents.get(self.current_document) {
+            Some(document) => Some(&*document.file),
+            None => self.root_file,
+        };
+        Location {
+            file: file.map(Path::to_owned),
+            pointer: self.current_pointer.clone(),
+        }
+    }
+
+    /// Records `error`, expansion carries on so only the first error is returned.
+    fn fail(&self, error: Error) {
+        self.error.borrow_mut().get_or_insert(error);
+    }
+
+    /// Calls `f` with `tokens` appended to the JSON pointer of the subschema being expanded.
+    fn at<T>(&mut self, tokens: &[&str], f: impl FnOnce(&mut Self) -> T) -> T {
+        let len = self.current_pointer.len();
+        for token in tokens {
+            self.current_pointer.push('/');
+            self.current_pointer
+                .push_str(&token.replace('~', "~0").replace('/', "~1"));
+        }
+        let result = f(self);
+        self.current_pointer.truncate(len);
+        result
+    }
+
+    /// Splits the reference `s` into the document it refers to, `""` for the root document, and
+    /// its fragment.
+    fn split_ref<'s>(&self, s: &'s str) -> (&'s str, String) {
+        let document = match s.split_once('#') {
+            Some((uri, _)) if self.documents.contains_key(uri) => uri,
+            _ => "",
+        };
+        (document, ref_fragment(s))
+    }
+
+    fn type_ref(&self, s: &str) -> String {
+        let (document, fragment) = self.split_ref(s);
+        let anchor = format!("{}#{}", document, fragment);
+        let tokens = pointer_tokens(&fragment);
+
+        let ref_ = match self.anchors.get(&anchor) {
+            Some((Some(name), _)) => name,
+            _ if fragment.is_empty() || self.anchors.contains_key(&anchor) => {
+                match (self.documents.get(document), self.root_name) {
+                    (Some(_), _) => match self.document_names.get(&(document.to_owned(), None)) {
+                        Some(name) => name,
+                        None => &self.documents[document].name,
+                    },
+                    (None, Some(root_name)) => root_name,
+                    (None, None) => {
+                        self.fail(Error::MissingRootName {
+                            location: self.location(),
+                        });
+                        return self.value_type();
+                    }
+                }
+            }
+            _ => match tokens {
+                Some(ref tokens) if !is_definition_pointer(tokens) => {
+                    return self.pointer_type_ref(document, &anchor, tokens);
+                }
+                Some(ref tokens) => {
+                    let key = tokens.last().map_or("", |token| token);
+                    let document_key = (document.to_owned(), Some(key.to_owned()));
+                    self.document_names
+                        .get(&document_key)
+                        .map_or(key, |name| name)
+                }
+                None => fragment
+                    .rsplit_once('/')
+                    .map_or(&fragment[..], |(_, last)| last),
+            },
         };
 
         let ref_ = ref_.to_pascal_case();
@@ -378,11 +1026,142 @@ impl<'r> Expander<'r> {
         replace_numeric_start(&ref_)
     }
 
-    fn schema(&self, schema: &'r Schema) -> Cow<'r, Schema> {
+    /// The type of the subschema at the JSON pointer `tokens`, which is not a definition.
+    ///
+    /// The subschema is generated as a type of its own, shared by all references to it.
+    fn pointer_type_ref(&self, document: &str, ref_: &str, tokens: &[String]) -> String {
+        let key = pointer_key(document, tokens);
+        if let Some((_, name)) = self.pointer_types.borrow().get(&key) {
+            return name.clone();
+        }
+        // A reference to a reference is the type of the schema referenced last
+        if let Some(schema) = self.try_schema_ref(ref_) {
+            match schema.ref_ {
+                Some(ref target) if target != ref_ && !has_ref_siblings(&schema) => {
+                    return self.type_ref(target);
+                }
+                _ => (),
+            }
+        }
+
+        // Array elements are named like inline ones, `point/items/0` becomes `PointItem0`
+        let is_index = |token: &str| token.parse::<usize>().is_ok();
+        let name = match tokens {
+            [.., parent, items, index]
+                if (items == "items" || items == "prefixItems") && is_index(index) =>
+            {
+                format!("{}Item{}", parent, index)
+            }
+            [.., parent, items] if items == "items" => format!("{}Item", parent),
+            [.., array, index] if is_index(index) => format!("{}{}", array, index),
+            [.., last] => last.clone(),
+            [] => String::new(),
+        };
+        let name = replace_invalid_identifier_chars(&name.to_pascal_case());
+        let name = replace_numeric_start(&name);
+        let mut pointer_types = self.pointer_types.borrow_mut();
+        let mut unique = name.clone();
+        let mut i = 1;
+        while pointer_types.values().any(|(_, used)| *used == unique) {
+            i += 1;
+            unique = format!("{}{}", name, i);
+        }
+        pointer_types.insert(key, (ref_.to_owned(), unique.clone()));
+        unique
+    }
+
+    /// Names the subschemas which are referenced by a JSON pointer before expanding anything,
+    /// so the property or item defining such a subschema gets the shared type as well.
+    fn register_pointer_types(&self) {
+        let root = self
+            .root_name
+            .map_or_else(|| definition_schemas(self.root), |_| vec![self.root]);
+        let documents = self.documents.values().flat_map(|document| {
+            if document.own_module {
+                vec![]
+            } else if document.referenced {
+                vec![&document.schema]
+            } else {
+                definition_schemas(&document.schema)
+            }
+        });
+        let mut refs = Vec::new();
+        for schema in root.into_iter().chain(documents) {
+            let mut value = match serde_json::to_value(schema) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            walk_subschemas_mut(&mut value, &mut |schema| {
+                if let Some(Value::String(ref_)) = schema.get("$ref") {
+                    refs.push(ref_.clone());
+                }
+            });
+        }
+        for ref_ in refs {
+            let (document, fragment) = self.split_ref(&ref_);
+            let anchor = format!("{}#{}", document, fragment);
+            if self.anchors.contains_key(&anchor) {
+                continue;
+            }
+            match pointer_tokens(&fragment) {
+                Some(ref tokens) if !is_definition_pointer(tokens) => {
+                    self.pointer_type_ref(document, &anchor, tokens);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// The type generated for the subschema being expanded, if it is referenced by a JSON
+    /// pointer, unless it is that type which is being generated.
+    fn current_pointer_type(&self) -> Option<String> {
+        let tokens = self
+            .current_pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect::<Vec<_>>();
+        let key = pointer_key(self.current_document, &tokens);
+        let pointer_types = self.pointer_types.borrow();
+        let (_, name) = pointer_types.get(&key)?;
+        if *name == self.current_type {
+            return None;
+        }
+        Some(name.clone())
+    }
+
+    fn schema<'s>(&self, schema: &'s Schema) -> Cow<'s, Schema>
+    where
+        'r: 's,
+    {
         let schema = match schema.ref_ {
+            // From 2019-09 on `$ref` is an applicator like `allOf`, so keywords next to it apply
+            // as well
+            Some(ref ref_) if self.draft >= Draft::Draft201909 && has_ref_siblings(schema) => {
+                let mut result = self.schema(&self.schema_ref(ref_)).into_owned();
+                let mut siblings = schema.clone();
+                siblings.ref_ = None;
+                siblings.all_of = None;
+                merge_all_of(&mut result, &siblings);
+                for def in schema.all_of.iter().flatten() {
+                    merge_all_of(&mut result, &self.schema(def));
+                }
+                return Cow::Owned(result);
+            }
             Some(ref ref_) => self.schema_ref(ref_),
-            None => schema,
+            None => Cow::Borrowed(schema),
         };
+        match schema {
+            Cow::Borrowed(schema) => self.merge_all_of(schema),
+            Cow::Owned(schema) => Cow::Owned(self.merge_all_of(&schema).into_owned()),
+        }
+    }
+
+    /// Merges the `allOf` subschemas of `schema`.
+    fn merge_all_of<'s>(&self, schema: &'s Schema) -> Cow<'s, Schema>
+    where
+        'r: 's,
+    {
         match schema.all_of {
             Some(ref all_of) if !all_of.is_empty() => {
                 all_of
@@ -397,21 +1176,59 @@ impl<'r> Expander<'r> {
         }
     }
 
-    fn schema_ref(&self, s: &str) -> &'r Schema {
-        s.split('/').fold(self.root, |schema, comp| {
-            if comp.ends_with('#') {
-                self.root
-            } else if comp == "definitions" {
-                schema
-            } else {
-                schema
-                    .definitions
-                    .get(comp)
-                    .unwrap_or_else(|| panic!("Expected definition: `{}` {}", s, comp))
-            }
+    fn schema_ref(&self, s: &str) -> Cow<'r, Schema> {
+        self.try_schema_ref(s).unwrap_or_else(|| {
+            self.fail(Error::UnresolvedRef {
+                location: self.location(),
+                reference: s.to_owned(),
+            });
+            Cow::Owned(empty_schema())
         })
     }
 
+    fn try_schema_ref(&self, s: &str) -> Option<Cow<'r, Schema>> {
+        let (document, fragment) = self.split_ref(s);
+        if let Some((_, schema)) = self.anchors.get(&format!("{}#{}", document, fragment)) {
+            return Some(Cow::Borrowed(schema));
+        }
+
+        let (mut schema, value) = match self.documents.get(document) {
+            Some(document) => (&document.schema, Some(&document.value)),
+            None => (self.root, self.root_value),
+        };
+        let tokens = match pointer_tokens(&fragment) {
+            Some(tokens) => tokens,
+            None if fragment.is_empty() => return Some(Cow::Borrowed(schema)),
+            // Not a JSON pointer, look it up as a (nested) definition
+            None => {
+                let mut definitions = &schema.definitions;
+                for comp in fragment.split('/').filter(|comp| !comp.is_empty()) {
+                    match comp {
+                        "definitions" => definitions = &schema.definitions,
+                        "$defs" => definitions = &schema.defs,
+                        _ => {
+                            schema = definitions.get(comp)?;
+                            definitions = &schema.definitions;
+                        }
+                    }
+                }
+                return Some(Cow::Borrowed(schema));
+            }
+        };
+        if let Some(schema) = walk_pointer(schema, &tokens) {
+            return Some(Cow::Borrowed(schema));
+        }
+
+        // The pointer leads through values which are not stored as a `Schema`
+        let value = match value {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(serde_json::to_value(schema).ok()?),
+        };
+        let pointer = percent_decode(&fragment);
+        let schema = serde_json::from_value(value.pointer(&pointer)?.clone()).ok()?;
+        Some(Cow::Owned(schema))
+    }
+
     fn expand_type(&mut self, type_name: &str, required: bool, typ: &Schema) -> FieldType {
         let saved_type = self.current_type.clone();
         let mut result = self.expand_type_(typ);
@@ -432,16 +1249,34 @@ impl<'r> Expander<'r> {
         result
     }
 
+    #[allow(clippy::get_first, clippy::unnecessary_map_or)]
     fn expand_type_(&mut self, typ: &Schema) -> FieldType {
+        if let Some(name) = self.current_pointer_type() {
+            return name.into();
+        }
         if let Some(ref ref_) = typ.ref_ {
+            if self.draft >= Draft::Draft201909 && has_ref_siblings(typ) {
+                let mut typ = typ.clone();
+                let target = self.schema_ref(ref_);
+                typ.ref_ = None;
+                let mut merged = self.schema(&target).into_owned();
+                merge_all_of(&mut merged, &typ);
+                return self.expand_type_(&merged);
+            }
             self.type_ref(ref_).into()
+        } else if is_false_schema(typ) {
+            format!("{}Never", self.schemafy_path).into()
         } else if typ.any_of.as_ref().map_or(false, |a| a.len() >= 2) {
             let any_of = typ.any_of.as_ref().unwrap();
             let simple = self.schema(&any_of[0]);
             let array = self.schema(&any_of[1]);
             if !array.type_.is_empty() {
                 if let SimpleTypes::Array = array.type_[0] {
-                    if simple == self.schema(&array.items[0]) {
+                    if array
+                        .items
+                        .first()
+                        .is_some_and(|item| simple == self.schema(item))
+                    {
                         return FieldType {
                             typ: format!("Vec<{}>", self.expand_type_(&any_of[0]).typ),
                             attributes: vec![format!(
@@ -453,12 +1288,48 @@ impl<'r> Expander<'r> {
                     }
                 }
             }
-            "serde_json::Value".into()
+            self.value_type().into()
         } else if typ.one_of.as_ref().map_or(false, |a| a.len() >= 2) {
             let schemas = typ.one_of.as_ref().unwrap();
-            let (type_name, type_def) = self.expand_one_of(schemas);
+            let (type_name, type_def) = self.expand_one_of(schemas, &[], Some("oneOf"), true);
             self.types.push((type_name.clone(), type_def));
             type_name.into()
+        } else if is_conditional(typ) {
+            let schemas = self.conditional_variants(typ);
+            let (type_name, type_def) =
+                self.expand_one_of(&schemas, &["Then", "Else"], None, false);
+            let deserialize = self.conditional_deserialize(&type_name, typ);
+            self.types.push((
+                type_name.clone(),
+                quote! {
+                    #type_def
+                    #deserialize
+                },
+            ));
+            type_name.into()
+        } else if let Some(ref value) = typ.const_ {
+            match value {
+                // A single variant enum which only accepts the exact value
+                Value::String(_) | Value::Bool(_) | Value::Number(_) => {
+                    let name = self.inline_type_name();
+                    let tokens = self.expand_schema(&name, typ);
+                    self.types.push((name.clone(), tokens));
+                    name.into()
+                }
+                _ => self.value_type().into(),
+            }
+        } else if typ.type_.is_empty() && is_scalar_enum(typ) {
+            // An enum of values of different types, defined inline
+            self.expand_inline_enum(typ).into()
+        } else if typ.type_.is_empty() && (typ.contains.is_some() || typ.property_names.is_some()) {
+            // `contains` only applies to arrays and `propertyNames` only to objects
+            let mut typ = typ.clone();
+            typ.type_ = vec![if typ.contains.is_some() {
+                SimpleTypes::Array
+            } else {
+                SimpleTypes::Object
+            }];
+            self.expand_type_(&typ)
         } else if typ.type_.len() == 2 {
             if typ.type_[0] == SimpleTypes::Null || typ.type_[1] == SimpleTypes::Null {
                 let mut ty = typ.clone();
@@ -470,30 +1341,49 @@ impl<'r> Expander<'r> {
                     default: true,
                 }
             } else {
-                "serde_json::Value".into()
+                self.value_type().into()
             }
         } else if typ.type_.len() == 1 {
             match typ.type_[0] {
+                SimpleTypes::Integer | SimpleTypes::Number | SimpleTypes::Boolean
+                    if is_scalar_enum(typ) =>
+                {
+                    self.expand_inline_enum(typ).into()
+                }
                 SimpleTypes::String => {
                     if typ.enum_.as_ref().map_or(false, |e| e.is_empty()) {
-                        "serde_json::Value".into()
+                        self.value_type().into()
+                    } else if typ.enum_.is_some() {
+                        self.expand_inline_enum(typ).into()
                     } else {
-                        "String".into()
+                        typ.format
+                            .as_deref()
+                            .and_then(|format| self.format_type(format))
+                            .unwrap_or_else(|| "String".into())
+                            .into()
                     }
                 }
+                SimpleTypes::Integer if self.narrow_integers => integers::integer_type(typ).into(),
                 SimpleTypes::Integer => "i64".into(),
                 SimpleTypes::Boolean => "bool".into(),
-                SimpleTypes::Number => "f64".into(),
+                SimpleTypes::Number => {
+                    let user_type = typ
+                        .format
+                        .as_ref()
+                        .and_then(|format| self.formats.get(format));
+                    match (user_type, typ.format.as_deref()) {
+                        (Some(user_type), _) => user_type.clone().into(),
+                        (None, Some("decimal")) => {
+                            self.number_type.exact().path(self.schemafy_path).into()
+                        }
+                        (None, _) => self.number_type.path(self.schemafy_path).into(),
+                    }
+                }
                 // Handle objects defined inline
                 SimpleTypes::Object
-                    if !typ.properties.is_empty()
-                        || typ.additional_properties == Some(Value::Bool(false)) =>
+                    if !typ.properties.is_empty() || denies_unknown_properties(typ) =>
                 {
-                    let name = format!(
-                        "{}{}",
-                        self.current_type.to_pascal_case(),
-                        self.current_field.to_pascal_case()
-                    );
+                    let name = self.inline_type_name();
                     let tokens = self.expand_schema(&name, typ);
                     self.types.push((name.clone(), tokens));
                     name.into()
@@ -501,33 +1391,184 @@ impl<'r> Expander<'r> {
                 SimpleTypes::Object => {
                     let prop = match typ.additional_properties {
                         Some(ref props) if props.is_object() => {
-                            let prop = serde_json::from_value(props.clone()).unwrap();
-                            self.expand_type_(&prop).typ
+                            self.at(&["additionalProperties"], |expander| {
+                                match serde_json::from_value(props.clone()) {
+                                    Ok(prop) => expander.expand_type_(&prop).typ,
+                                    Err(source) => {
+                                        expander.fail(Error::InvalidSubschema {
+                                            location: expander.location(),
+                                            source,
+                                        });
+                                        expander.value_type()
+                                    }
+                                }
+                            })
                         }
-                        _ => "serde_json::Value".into(),
+                        _ => self.value_type(),
                     };
-                    let result = format!("::std::collections::BTreeMap<String, {}>", prop);
+                    let result = format!("{}<String, {}>", self.map_type, prop);
                     FieldType {
                         typ: result,
                         attributes: Vec::new(),
                         default: typ.default == Some(Value::Object(Default::default())),
                     }
                 }
+                SimpleTypes::Array
+                    if self.draft >= Draft::Draft202012 && typ.prefix_items.is_some() =>
+                {
+                    self.expand_prefix_items(typ).into()
+                }
                 SimpleTypes::Array => {
-                    let item_type = typ.items.get(0).map_or("serde_json::Value".into(), |item| {
+                    // Without `items` nothing is known about the elements, even if some of them
+                    // must match `contains`
+                    let item_type = typ.items.get(0).map_or(self.value_type(), |item| {
                         self.current_type = format!("{}Item", self.current_type);
-                        self.expand_type_(item).typ
+                        self.at(&["items"], |expander| expander.expand_type_(item).typ)
                     });
                     format!("Vec<{}>", item_type).into()
                 }
-                _ => "serde_json::Value".into(),
+                _ => self.value_type().into(),
             }
         } else {
-            "serde_json::Value".into()
+            self.value_type().into()
         }
     }
 
-    fn expand_one_of(&mut self, schemas: &[Schema]) -> (String, TokenStream) {
+    /// Expands a 2020-12 array with `prefixItems` into a tuple, or into a `TupleRest` if the
+    /// tuple may be followed by more `items`.
+    ///
+    /// `TupleRest` supports at most 12 prefix items, longer prefixes become a `Vec` of values.
+    fn expand_prefix_items(&mut self, typ: &Schema) -> String {
+        if typ.prefix_items.as_ref().map_or(0, |items| items.len()) > MAX_PREFIX_ITEMS {
+            return format!("Vec<{}>", self.value_type());
+        }
+        let saved_type = self.current_type.clone();
+        let prefix = typ
+            .prefix_items
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, item)| {
+                self.current_type = format!("{}Item{}", saved_type, i);
+                self.at(&["prefixItems", &i.to_string()], |expander| {
+                    expander.expand_type_(item).typ
+                })
+            })
+            .collect::<Vec<_>>();
+        let prefix = if prefix.len() == 1 {
+            format!("({},)", prefix[0])
+        } else {
+            format!("({})", prefix.join(", "))
+        };
+        let result = match typ.items.first() {
+            // `items: false` forbids any elements after the tuple
+            Some(item) if is_false_schema(item) => prefix,
+            Some(item) => {
+                self.current_type = format!("{}Item", saved_type);
+                let rest = self.at(&["items"], |expander| expander.expand_type_(item).typ);
+                format!("{}TupleRest<{}, {}>", self.schemafy_path, prefix, rest)
+            }
+            // Without `items` any elements may follow the tuple
+            None => format!(
+                "{}TupleRest<{}, {}>",
+                self.schemafy_path,
+                prefix,
+                self.value_type()
+            ),
+        };
+        self.current_type = saved_type;
+        result
+    }
+
+    /// Splits a schema using `if`/`then`/`else` into the two shapes a value can have, one where
+    /// `if` (and `then`) holds and one where `else` does.
+    fn conditional_variants(&self, schema: &Schema) -> Vec<Schema> {
+        let mut base = schema.clone();
+        base.if_ = None;
+        base.then = None;
+        base.else_ = None;
+        base.definitions.clear();
+        base.defs.clear();
+
+        let mut then = base.clone();
+        for sub_schema in schema.if_.iter().chain(&schema.then) {
+            merge_all_of(&mut then, &self.schema(sub_schema));
+        }
+
+        let mut else_ = base;
+        if let Some(ref sub_schema) = schema.else_ {
+            merge_all_of(&mut else_, &self.schema(sub_schema));
+        }
+
+        vec![then, else_]
+    }
+
+    /// A `Deserialize` implementation for the enum `name` of the variants of `schema`, which
+    /// picks the `Then` variant for values matching `if`, rather than the first variant the value
+    /// fits. A value matching `if` but not `then` is rejected.
+    ///
+    /// Whether a value matches `if` is decided by deserializing it as a type generated for `if`,
+    /// without `strict` as other fields are allowed.
+    fn conditional_deserialize(&mut self, name: &str, schema: &Schema) -> TokenStream {
+        let condition = match schema.if_ {
+            Some(ref condition) => self.schema(condition).clone(),
+            None => return TokenStream::new(),
+        };
+        let if_name = format!("{}If", name);
+        let strict = std::mem::replace(&mut self.strict, false);
+        let saved_type = std::mem::replace(&mut self.current_type, if_name.clone());
+        let if_type = self.at(&["if"], |expander| {
+            expander.expand_schema(&if_name, &condition)
+        });
+        self.current_type = saved_type;
+        self.strict = strict;
+        self.types.push((if_name.clone(), if_type));
+
+        let name = syn::Ident::new(name, Span::call_site());
+        let if_type = format_ident!("{}", if_name);
+        let then_type = format_ident!("{}Then", name);
+        let else_type = format_ident!("{}Else", name);
+        let serde_path = self.parse_code::<syn::Path>(&self.serde_path());
+        let value_type = self.parse_code::<syn::Type>(&self.value_type());
+        quote! {
+            impl<'de> #serde_path::Deserialize<'de> for #name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: #serde_path::Deserializer<'de>,
+                {
+                    let value = <#value_type as #serde_path::Deserialize>::deserialize(deserializer)?;
+                    let result = if <#if_type as #serde_path::Deserialize>::deserialize(&value).is_ok() {
+                        <#then_type as #serde_path::Deserialize>::deserialize(value).map(#name::Then)
+                    } else {
+                        <#else_type as #serde_path::Deserialize>::deserialize(value).map(#name::Else)
+                    };
+                    result.map_err(<D::Error as #serde_path::de::Error>::custom)
+                }
+            }
+        }
+    }
+
+    /// The name of a type defined inline in the field currently being expanded.
+    fn inline_type_name(&self) -> String {
+        format!(
+            "{}{}",
+            self.current_type.to_pascal_case(),
+            self.current_field.to_pascal_case()
+        )
+    }
+
+    /// Expands the `schemas` one of which a value matches into an untagged enum.
+    ///
+    /// `names` overrides the names of the first variants. `keyword` is the keyword holding the
+    /// schemas, `None` if they do not appear in the document as they are (for `if`/`then`/`else`).
+    /// `Deserialize` is only derived if `deserialize` is set, otherwise the caller implements it.
+    fn expand_one_of(
+        &mut self,
+        schemas: &[Schema],
+        names: &[&str],
+        keyword: Option<&str>,
+        deserialize: bool,
+    ) -> (String, TokenStream) {
         let current_field = if self.current_field.is_empty() {
             "".to_owned()
         } else {
@@ -543,23 +1584,41 @@ impl<'r> Expander<'r> {
             .iter()
             .enumerate()
             .map(|(i, schema)| {
-                let name = schema.id.clone().unwrap_or_else(|| format!("Variant{}", i));
+                let name = names
+                    .get(i)
+                    .map(|name| name.to_string())
+                    .or_else(|| schema.id.clone())
+                    .unwrap_or_else(|| format!("Variant{}", i));
                 if let Some(ref_) = &schema.ref_ {
                     let type_ = self.type_ref(ref_);
                     (format_ident!("{}", &name), format_ident!("{}", &type_))
                 } else {
                     let type_name = format!("{}{}", saved_type, &name);
-                    let field_type = self.expand_schema(&type_name, schema);
+                    let index = i.to_string();
+                    let tokens = match keyword {
+                        Some(keyword) => vec![keyword, index.as_str()],
+                        None => vec![],
+                    };
+                    let field_type = self.at(&tokens, |expander| {
+                        expander.expand_schema(&type_name, schema)
+                    });
                     self.types.push((type_name.clone(), field_type));
                     (format_ident!("{}", &name), format_ident!("{}", &type_name))
                 }
             })
             .unzip();
         let type_name_ident = syn::Ident::new(&saved_type, Span::call_site());
+        let derives: &[&str] = if deserialize {
+            &["Clone", "PartialEq", "Debug", "Deserialize", "Serialize"]
+        } else {
+            &["Clone", "PartialEq", "Debug", "Serialize"]
+        };
+        let derive = self.type_attributes(&saved_type, derives);
+        let visibility = &self.visibility;
         let type_def = quote! {
-            #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+            #derive
             #[serde(untagged)]
-            pub enum #type_name_ident {
+            #visibility enum #type_name_ident {
                 #(#variant_names(#variant_types)),*
             }
         };
@@ -567,11 +1626,21 @@ impl<'r> Expander<'r> {
     }
 
     fn expand_definitions(&mut self, schema: &Schema) {
-        for (name, def) in &schema.definitions {
-            let type_decl = self.expand_schema(name, def);
-            let definition_tokens = match def.description {
-                Some(ref comment) => {
-                    let t = make_doc_comment(comment, LINE_LENGTH);
+        let definitions = schema.definitions.iter().map(|def| ("definitions", def));
+        let defs = schema.defs.iter().map(|def| ("$defs", def));
+        for (keyword, (key, def)) in definitions.chain(defs) {
+            let document_key = (self.current_document.to_owned(), Some(key.clone()));
+            let name = self
+                .document_names
+                .get(&document_key)
+                .unwrap_or(key)
+                .clone();
+            let type_decl = self.at(&[keyword, key], |expander| {
+                expander.expand_schema(&name, def)
+            });
+            let definition_tokens = match doc_text(def) {
+                Some(comment) => {
+                    let t = make_doc_comment(&comment, LINE_LENGTH);
                     quote! {
                         #t
                         #type_decl
@@ -584,10 +1653,19 @@ impl<'r> Expander<'r> {
     }
 
     fn expand_schema(&mut self, original_name: &str, schema: &Schema) -> TokenStream {
+        // The output is discarded after an error, and the placeholders used in place of the
+        // failing schemas could otherwise be expanded forever
+        if self.error.borrow().is_some() {
+            return TokenStream::new();
+        }
         self.expand_definitions(schema);
 
         let pascal_case_name = replace_invalid_identifier_chars(&original_name.to_pascal_case());
         self.current_type.clone_from(&pascal_case_name);
+        let name = syn::Ident::new(&pascal_case_name, Span::call_site());
+        if is_conditional(schema) {
+            return self.expand_alias(&name, schema);
+        }
         let (fields, default) = {
             let mut field_expander = FieldExpander {
                 default: true,
@@ -596,9 +1674,7 @@ impl<'r> Expander<'r> {
             let fields = field_expander.expand_fields(original_name, schema);
             (fields, field_expander.default)
         };
-        let name = syn::Ident::new(&pascal_case_name, Span::call_site());
-        let is_struct =
-            !fields.is_empty() || schema.additional_properties == Some(Value::Bool(false));
+        let is_struct = !fields.is_empty() || denies_unknown_properties(schema);
         let serde_rename = if name == original_name {
             None
         } else {
@@ -606,168 +1682,274 @@ impl<'r> Expander<'r> {
                 #[serde(rename = #original_name)]
             })
         };
-        let is_enum = schema.enum_.as_ref().map_or(false, |e| !e.is_empty());
+        let visibility = self.visibility.clone();
+        let is_enum = !enum_values(schema).is_empty()
+            && (schema.enum_.is_some()
+                || matches!(
+                    schema.const_,
+                    Some(Value::String(_) | Value::Bool(_) | Value::Number(_))
+                ));
         let type_decl = if is_struct {
-            let serde_deny_unknown = if schema.additional_properties == Some(Value::Bool(false))
-                && schema.pattern_properties.is_empty()
-            {
+            let allows_unknown =
+                schema.additional_properties.is_some() || schema.unevaluated_properties.is_some();
+            let deny_unknown = denies_unknown_properties(schema) || self.strict && !allows_unknown;
+            let serde_deny_unknown = if deny_unknown && schema.pattern_properties.is_empty() {
                 Some(quote! { #[serde(deny_unknown_fields)] })
             } else {
                 None
             };
-            if default {
-                quote! {
-                    #[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
-                    #serde_rename
-                    #serde_deny_unknown
-                    pub struct #name {
-                        #(#fields),*
-                    }
-                }
+            let derive = if default {
+                self.type_attributes(
+                    &pascal_case_name,
+                    &[
+                        "Clone",
+                        "PartialEq",
+                        "Debug",
+                        "Default",
+                        "Deserialize",
+                        "Serialize",
+                    ],
+                )
             } else {
-                quote! {
-                    #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
-                    #serde_rename
-                    #serde_deny_unknown
-                    pub struct #name {
-                        #(#fields),*
-                    }
+                self.type_attributes(
+                    &pascal_case_name,
+                    &["Clone", "PartialEq", "Debug", "Deserialize", "Serialize"],
+                )
+            };
+            quote! {
+                #derive
+                #serde_rename
+                #serde_deny_unknown
+                #visibility struct #name {
+                    #(#fields),*
                 }
             }
         } else if is_enum {
-            let mut optional = false;
-            let mut repr_i64 = false;
-            let variants = if schema.enum_names.as_ref().map_or(false, |e| !e.is_empty()) {
-                let values = schema.enum_.as_ref().map_or(&[][..], |v| v);
-                let names = schema.enum_names.as_ref().map_or(&[][..], |v| v);
+            let values = enum_values(schema);
+            let names = schema.enum_names.as_ref().filter(|names| !names.is_empty());
+            if let Some(names) = names {
                 if names.len() != values.len() {
-                    panic!(
-                        "enumNames(length {}) and enum(length {}) have different length",
-                        names.len(),
-                        values.len()
-                    )
+                    self.fail(Error::EnumNamesLength {
+                        location: self.location(),
+                        names: names.len(),
+                        values: values.len(),
+                    });
                 }
-                names
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, name)| (&values[idx], name))
-                    .flat_map(|(value, name)| {
-                        let pascal_case_variant = name.to_pascal_case();
-                        let variant_name =
-                            rename_keyword("", &pascal_case_variant).unwrap_or_else(|| {
-                                let v = syn::Ident::new(&pascal_case_variant, Span::call_site());
-                                quote!(#v)
-                            });
-                        match value {
-                            Value::String(ref s) => Some(quote! {
-                                #[serde(rename = #s)]
-                                #variant_name
-                            }),
-                            Value::Number(ref n) => {
-                                repr_i64 = true;
-                                let num = syn::LitInt::new(&n.to_string(), Span::call_site());
-                                Some(quote! {
-                                    #variant_name = #num
-                                })
-                            }
-                            Value::Null => {
-                                optional = true;
-                                None
-                            }
-                            _ => panic!("Expected string,bool or number for enum got `{}`", value),
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            } else {
-                schema
-                    .enum_
-                    .as_ref()
-                    .map_or(&[][..], |v| v)
-                    .iter()
-                    .flat_map(|v| match *v {
-                        Value::String(ref v) => {
-                            let pascal_case_variant = v.to_pascal_case();
-                            let variant_name = rename_keyword("", &pascal_case_variant)
-                                .unwrap_or_else(|| {
-                                    let v =
-                                        syn::Ident::new(&pascal_case_variant, Span::call_site());
-                                    quote!(#v)
-                                });
-                            Some(if pascal_case_variant == *v {
-                                variant_name
-                            } else {
-                                quote! {
-                                    #[serde(rename = #v)]
-                                    #variant_name
-                                }
-                            })
-                        }
-                        Value::Null => {
-                            optional = true;
-                            None
-                        }
-                        _ => panic!("Expected string for enum got `{}`", v),
-                    })
-                    .collect::<Vec<_>>()
-            };
-            if optional {
-                let enum_name = syn::Ident::new(&format!("{}_", name), Span::call_site());
-                if repr_i64 {
-                    quote! {
-                        pub type #name = Option<#enum_name>;
-                        #[derive(Clone, PartialEq, Debug, Serialize_repr, Deserialize_repr)]
-                        #serde_rename
-                        #[repr(i64)]
-                        pub enum #enum_name {
-                            #(#variants),*
-                        }
-                    }
-                } else {
-                    quote! {
-                        pub type #name = Option<#enum_name>;
-                        #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
-                        #serde_rename
-                        pub enum #enum_name {
-                            #(#variants),*
-                        }
+            }
+            let mut optional = false;
+            let mut variants = Vec::new();
+            let mut variant_names = BTreeSet::new();
+            for (i, value) in values.iter().enumerate() {
+                match value {
+                    Value::Null => optional = true,
+                    Value::String(_) | Value::Number(_) | Value::Bool(_) => {
+                        let variant_name = match names {
+                            Some(names) => match names.get(i) {
+                                Some(name) => name.to_pascal_case(),
+                                None => continue,
+                            },
+                            None => enums::variant_name(value),
+                        };
+                        let variant_name = enums::unique_name(
+                            str_to_ident(&variant_name).to_string(),
+                            &mut variant_names,
+                        );
+                        variants.push((syn::Ident::new(&variant_name, Span::call_site()), value));
                     }
+                    _ => self.fail(Error::InvalidEnumValue {
+                        location: self.location(),
+                        value: value.clone(),
+                    }),
                 }
-            } else if repr_i64 {
-                quote! {
-                    #[derive(Clone, PartialEq, Debug, Serialize_repr, Deserialize_repr)]
-                    #serde_rename
-                    #[repr(i64)]
-                    pub enum #name {
-                        #(#variants),*
+            }
+            // Strings are (de)serialized by derives, anything else by the implementations below
+            let strings = variants.iter().all(|(_, value)| value.is_string());
+            let repr_i64 = !strings && variants.iter().all(|(_, value)| value.is_i64());
+            let literals = !strings && !repr_i64;
+            let variant_decls = variants.iter().map(|(variant_name, value)| match value {
+                Value::String(s) if strings && variant_name != s => quote! {
+                    #[serde(rename = #s)]
+                    #variant_name
+                },
+                Value::Number(n) if repr_i64 => {
+                    let num = syn::LitInt::new(&n.to_string(), Span::call_site());
+                    quote! {
+                        #variant_name = #num
                     }
                 }
+                _ => quote!(#variant_name),
+            });
+            let derive = if repr_i64 || literals {
+                self.type_attributes(&pascal_case_name, &["Clone", "PartialEq", "Debug"])
             } else {
+                self.type_attributes(
+                    &pascal_case_name,
+                    &["Clone", "PartialEq", "Debug", "Deserialize", "Serialize"],
+                )
+            };
+            let repr = if repr_i64 {
+                Some(quote!(#[repr(i64)]))
+            } else {
+                None
+            };
+            let enum_name = if optional {
+                syn::Ident::new(&format!("{}_", name), Span::call_site())
+            } else {
+                name.clone()
+            };
+            let serde_path = self.parse_code::<syn::Path>(&self.serde_path());
+            // Only the derives use the renamed type name, the other values are matched as they are
+            let serde_rename = serde_rename.filter(|_| strings);
+            let mut enum_decl = quote! {
+                #derive
+                #serde_rename
+                #repr
+                #visibility enum #enum_name {
+                    #(#variant_decls),*
+                }
+            };
+            if repr_i64 {
+                let impls = enums::repr_impls(&enum_name, &variants, &serde_path);
+                enum_decl = quote! {
+                    #enum_decl
+                    #impls
+                };
+            } else if literals {
+                let value_type = self.parse_code::<syn::Type>(&self.value_type());
+                let impls = enums::literal_impls(&enum_name, &variants, &serde_path, &value_type);
+                enum_decl = quote! {
+                    #enum_decl
+                    #impls
+                };
+            }
+            if optional {
                 quote! {
-                    #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
-                    #serde_rename
-                    pub enum #name {
-                        #(#variants),*
-                    }
+                    #visibility type #name = Option<#enum_name>;
+                    #enum_decl
                 }
+            } else {
+                enum_decl
             }
         } else {
-            let typ = self
-                .expand_type("", true, schema)
-                .typ
-                .parse::<TokenStream>()
-                .unwrap();
-            // Skip self-referential types, e.g. `struct Schema = Schema`
-            if name == typ.to_string() {
-                return TokenStream::new();
-            }
-            return quote! {
-                pub type #name = #typ;
-            };
+            return self.expand_alias(&name, schema);
         };
         type_decl
     }
 
+    fn expand_alias(&mut self, name: &syn::Ident, schema: &Schema) -> TokenStream {
+        self.current_field.clear();
+        let typ = self.expand_type("", true, schema).typ;
+        let typ = self.parse_code::<syn::Type>(&typ);
+        // Skip self-referential types, e.g. `struct Schema = Schema`
+        if *name == typ.to_string() {
+            return TokenStream::new();
+        }
+        let visibility = &self.visibility;
+        quote! {
+            #visibility type #name = #typ;
+        }
+    }
+
+    /// Generates the enum `typ` defined inline, named like inline objects, and returns its name.
+    fn expand_inline_enum(&mut self, typ: &Schema) -> String {
+        let name = self.inline_type_name();
+        let tokens = self.expand_schema(&name, typ);
+        self.types.push((name.clone(), tokens));
+        name
+    }
+
+    /// The type of strings with `format`, if it is not `String`.
+    fn format_type(&self, format: &str) -> Option<String> {
+        match self.formats.get(format) {
+            Some(typ) => Some(typ.clone()),
+            None => formats::format_type(format, &self.format_crates, self.schemafy_path),
+        }
+    }
+
+    /// The type of values which may be any JSON.
+    fn value_type(&self) -> String {
+        format!("{}serde_json::Value", self.schemafy_path)
+    }
+
+    fn serde_path(&self) -> String {
+        match self.serde_path {
+            Some(serde_path) => serde_path.to_owned(),
+            None => format!("{}serde", self.schemafy_path),
+        }
+    }
+
+    /// The `#[derive]` and other attributes of the generated type `name`, deriving `derives`
+    /// unless the type options say otherwise.
+    ///
+    /// The serde derives are expanded to full paths, so the generated code does not depend on
+    /// what is imported where it is included.
+    fn type_attributes(&self, name: &str, derives: &[&str]) -> TokenStream {
+        let mut all = derives
+            .iter()
+            .map(|derive| derive.to_string())
+            .collect::<Vec<_>>();
+        let mut attributes = Vec::new();
+        let named_options = self.named_type_options.get(name);
+        for options in Some(&self.type_options).into_iter().chain(named_options) {
+            all.retain(|derive| !options.removed_derives.contains(derive));
+            for derive in &options.derives {
+                if !all.contains(derive) {
+                    all.push(derive.clone());
+                }
+            }
+            attributes.extend(
+                options
+                    .attributes
+                    .iter()
+                    .map(|attribute| self.parse_code::<TokenStream>(attribute)),
+            );
+        }
+        let serde_path = self.serde_path();
+        let mut serde_crate = None;
+        let all = all
+            .iter()
+            .map(|derive| {
+                let derive = match &derive[..] {
+                    "Deserialize" | "Serialize" => {
+                        serde_crate = Some(quote! { #[serde(crate = #serde_path)] });
+                        format!("{}::{}", serde_path, derive)
+                    }
+                    _ => derive.clone(),
+                };
+                self.parse_code::<syn::Path>(&derive)
+            })
+            .collect::<Vec<_>>();
+        let derive = if all.is_empty() {
+            None
+        } else {
+            Some(quote! { #[derive(#(#all),*)] })
+        };
+        quote! {
+            #derive
+            #serde_crate
+            #(#attributes)*
+        }
+    }
+
+    /// Generates the types for `schema`, panicking if they can not be generated.
     pub fn expand(&mut self, schema: &Schema) -> TokenStream {
+        self.try_expand(schema)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Generates the types for `schema`, returning the first error found if they can not be
+    /// generated.
+    pub fn try_expand(&mut self, schema: &Schema) -> Result<TokenStream, Error> {
+        let tokens = self.expand_all(schema);
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(tokens),
+        }
+    }
+
+    fn expand_all(&mut self, schema: &Schema) -> TokenStream {
+        self.register_document_names();
+        self.register_pointer_types();
         match self.root_name {
             Some(name) => {
                 let schema = self.expand_schema(name, schema);
@@ -775,17 +1957,150 @@ impl<'r> Expander<'r> {
             }
             None => self.expand_definitions(schema),
         }
+        for (uri, document) in self.documents.clone() {
+            self.current_document = uri;
+            if document.own_module {
+                continue;
+            }
+            if document.referenced {
+                let name = self.document_names[&(uri.to_owned(), None)].clone();
+                let tokens = self.expand_schema(&name, &document.schema);
+                self.types.push((name, tokens));
+            } else {
+                self.expand_definitions(&document.schema);
+            }
+        }
+        // Expanding a subschema referenced by a JSON pointer may find references to others
+        let mut expanded = std::collections::BTreeSet::new();
+        loop {
+            let next = self
+                .pointer_types
+                .borrow()
+                .iter()
+                .find(|(key, _)| !expanded.contains(*key))
+                .map(|(key, (ref_, name))| (key.clone(), ref_.clone(), name.clone()));
+            let (key, ref_, name) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            let (document, fragment) = self.split_ref(&ref_);
+            self.current_document = self
+                .documents
+                .get_key_value(document)
+                .map_or("", |(uri, _)| uri);
+            self.current_pointer = match pointer_tokens(&fragment) {
+                Some(_) => percent_decode(&fragment),
+                None => String::new(),
+            };
+            let schema = self.schema_ref(&ref_);
+            let tokens = self.expand_schema(&name, &schema);
+            self.types.push((name, tokens));
+            expanded.insert(key);
+        }
 
         let types = self.types.iter().map(|t| &t.1);
 
-        quote! {
+        let tokens = quote! {
             #( #types )*
+        };
+        let paths = comparisons::Paths {
+            schemafy_path: self.schemafy_path,
+            map_type: self.map_type,
+        };
+        let removed = |name: &str, derive: &str| self.removes_derive(name, derive);
+        let tokens = match comparisons::derive_comparisons(tokens.clone(), &paths, removed) {
+            Ok(tokens) => tokens,
+            Err(source) => {
+                self.fail(Error::Format { source });
+                tokens
+            }
+        };
+        if self.number_type == NumberType::Decimal {
+            // Fails to compile with a clear message without the `decimal` feature
+            let require = format!("{}require_decimal", self.schemafy_path);
+            let require = self.parse_code::<syn::Path>(&require);
+            quote! {
+                #require!();
+                #tokens
+            }
+        } else {
+            tokens
+        }
+    }
+
+    /// Names the types of the documents other than the root document and of their definitions,
+    /// which all share one namespace with the types of the root document.
+    ///
+    /// The root document keeps its names, a clashing name of another document is prefixed with
+    /// more and more of the path of the document until it is unique.
+    fn register_document_names(&mut self) {
+        let mut used = BTreeSet::new();
+        used.extend(self.root_name.map(str::to_owned));
+        let mut keys = Vec::new();
+        definition_keys(self.root, "", &mut keys);
+        used.extend(keys.iter().map(|(key, _)| type_name(key)));
+
+        let mut document_names = BTreeMap::new();
+
+        for (uri, document) in &self.documents {
+            if document.own_module {
+                continue;
+            }
+            let mut keys = Vec::new();
+            if document.referenced {
+                keys.push((None, document.name.clone(), String::new()));
+            }
+            let mut definitions = Vec::new();
+            definition_keys(&document.schema, "", &mut definitions);
+            keys.extend(
+                definitions
+                    .into_iter()
+                    .map(|(key, pointer)| (Some(key.clone()), key, pointer)),
+            );
+            for (key, name, pointer) in keys {
+                let qualified = qualified_keys(uri, &name, key.is_none())
+                    .into_iter()
+                    .find(|qualified| !used.contains(&type_name(qualified)));
+                let name = match qualified {
+                    Some(qualified) => qualified,
+                    None => {
+                        self.fail(Error::TypeNameClash {
+                            location: Location {
+                                file: Some(document.file.clone()),
+                                pointer,
+                            },
+                            name: type_name(&name),
+                        });
+                        name
+                    }
+                };
+                used.insert(type_name(&name));
+                document_names.insert((uri.to_string(), key), name);
+            }
         }
+        self.document_names = document_names;
+    }
+
+    /// Whether the type options remove `derive` from the type `name`.
+    fn removes_derive(&self, name: &str, derive: &str) -> bool {
+        Some(&self.type_options)
+            .into_iter()
+            .chain(self.named_type_options.get(name))
+            .any(|options| {
+                options
+                    .removed_derives
+                    .iter()
+                    .any(|removed| removed == derive)
+            })
     }
 
     pub fn expand_root(&mut self) -> TokenStream {
         self.expand(self.root)
     }
+
+    pub fn try_expand_root(&mut self) -> Result<TokenStream, Error> {
+        self.try_expand(self.root)
+    }
 }
 
 #[cfg(test)]
@@ -830,6 +2145,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn json_pointer_tokens() {
+        assert_eq!(pointer_tokens(""), None);
+        assert_eq!(pointer_tokens("definitions"), None);
+        assert_eq!(
+            pointer_tokens("/definitions/a~1b~0c"),
+            Some(vec!["definitions".into(), "a/b~c".into()])
+        );
+        assert_eq!(
+            pointer_tokens("/properties/with%20space/items/0"),
+            Some(vec![
+                "properties".into(),
+                "with space".into(),
+                "items".into(),
+                "0".into()
+            ])
+        );
+        assert!(is_definition_pointer(
+            &pointer_tokens("/$defs/a/definitions/b").unwrap()
+        ));
+        assert!(!is_definition_pointer(
+            &pointer_tokens("/definitions/a/properties/b").unwrap()
+        ));
+    }
+
     #[test]
     fn embedded_type_names() {
         use std::collections::HashSet;
@@ -0,0 +1,37 @@
+//! The Rust types of `"type": "number"`.
+
+/// The type generated for numbers, which are `f64`s unless their exact value has to be kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NumberType {
+    /// `f64`, which rounds numbers with more significant digits than it can hold.
+    #[default]
+    F64,
+    /// `serde_json::Number`, which keeps integers up to 64 bits exactly, and other numbers too
+    /// if the `arbitrary_precision` feature of `serde_json` is enabled.
+    Number,
+    /// `schemafy_core::Decimal`, a `rust_decimal::Decimal` (de)serialized as a number, which needs
+    /// the `decimal` feature of `schemafy_core` where the generated code is compiled. That feature
+    /// enables the `arbitrary_precision` feature of `serde_json` for the whole build, which
+    /// changes how every `serde_json::Value` and `serde_json::Number` holds numbers.
+    Decimal,
+}
+
+impl NumberType {
+    /// The path of the type, through `schemafy_path` for those `schemafy_core` re-exports.
+    pub(crate) fn path(self, schemafy_path: &str) -> String {
+        match self {
+            NumberType::F64 => "f64".into(),
+            NumberType::Number => format!("{}serde_json::Number", schemafy_path),
+            NumberType::Decimal => format!("{}Decimal", schemafy_path),
+        }
+    }
+
+    /// The type of numbers with the format `decimal`, which must be kept exactly: a `Decimal` if
+    /// numbers are one, or else a `serde_json::Number`.
+    pub(crate) fn exact(self) -> NumberType {
+        match self {
+            NumberType::Decimal => NumberType::Decimal,
+            NumberType::F64 | NumberType::Number => NumberType::Number,
+        }
+    }
+}
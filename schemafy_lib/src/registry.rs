@@ -0,0 +1,190 @@
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// Maps absolute URIs to the local files holding the schemas they identify, so `$ref`s to
+/// them can be resolved without network access.
+///
+/// A URI is mapped by replacing the longest prefix registered with [`Registry::insert`] by its
+/// directory. With `https://example.com/schemas/` mapped to `schemas`,
+/// `https://example.com/schemas/common/address.json` is read from `schemas/common/address.json`
+/// (`.json` is appended to URIs without an extension if that file exists instead).
+///
+/// The meta-schemas of the supported drafts are bundled and always resolve.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Registry {
+    prefixes: Vec<(String, PathBuf)>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Maps the URIs starting with `prefix` to the files in `directory`.
+    pub fn insert(&mut self, prefix: impl Into<String>, directory: impl Into<PathBuf>) {
+        self.prefixes.push((prefix.into(), directory.into()));
+    }
+
+    /// Adds the mappings of a catalog file.
+    ///
+    /// A catalog is a JSON object with URI prefixes as keys and directories, relative to the
+    /// catalog, as values:
+    ///
+    /// ```json
+    /// {
+    ///     "https://example.com/schemas/": "schemas"
+    /// }
+    /// ```
+    pub fn add_catalog(&mut self, catalog: impl AsRef<Path>) -> io::Result<()> {
+        let catalog = catalog.as_ref();
+        let json = std::fs::read_to_string(catalog)?;
+        let mappings: BTreeMap<String, PathBuf> = serde_json::from_str(&json)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let directory = catalog.parent().unwrap_or_else(|| Path::new(""));
+        for (prefix, path) in mappings {
+            self.insert(prefix, directory.join(path));
+        }
+        Ok(())
+    }
+
+    /// Finds the schema document identified by `uri`.
+    pub(crate) fn locate(&self, uri: &str) -> Option<Source> {
+        let mapped = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| uri.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len());
+        if let Some((prefix, directory)) = mapped {
+            let path = directory.join(&uri[prefix.len()..]);
+            if path.extension().is_none() && !path.exists() {
+                return Some(Source::File(path.with_extension("json")));
+            }
+            return Some(Source::File(path));
+        }
+
+        let path = uri
+            .strip_prefix("https://")
+            .or_else(|| uri.strip_prefix("http://"))?;
+        META_SCHEMAS
+            .iter()
+            .find(|(meta_schema, _)| *meta_schema == path)
+            .map(|(_, json)| Source::Bundled(uri.to_owned(), json))
+    }
+}
+
+/// The name used in place of a file for schemas passed as a string or value.
+pub(crate) const INLINE_SCHEMA: &str = "<inline schema>";
+
+/// Where a schema document is read from.
+pub(crate) enum Source {
+    File(PathBuf),
+    /// A meta-schema bundled with this crate, with its URI.
+    Bundled(String, &'static str),
+    /// A schema passed in memory, with the directory its relative references are resolved
+    /// against.
+    Inline(PathBuf),
+}
+
+impl Source {
+    /// The file the document is read from, or the URI of a bundled meta-schema.
+    pub(crate) fn file(&self) -> PathBuf {
+        match self {
+            Source::File(path) => path.clone(),
+            Source::Bundled(uri, _) => PathBuf::from(uri),
+            Source::Inline(_) => PathBuf::from(INLINE_SCHEMA),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.to_string_lossy()),
+            Source::Bundled(uri, _) => write!(f, "{}", uri),
+            Source::Inline(_) => write!(f, "{}", INLINE_SCHEMA),
+        }
+    }
+}
+
+/// The meta-schemas of the supported drafts, keyed by their URI without the scheme.
+const META_SCHEMAS: &[(&str, &str)] = &[
+    (
+        "json-schema.org/draft-04/schema",
+        include_str!("meta_schemas/draft-04/schema.json"),
+    ),
+    (
+        "json-schema.org/draft-06/schema",
+        include_str!("meta_schemas/draft-06/schema.json"),
+    ),
+    (
+        "json-schema.org/draft-07/schema",
+        include_str!("meta_schemas/draft-07/schema.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/schema",
+        include_str!("meta_schemas/draft/2019-09/schema.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/meta/applicator",
+        include_str!("meta_schemas/draft/2019-09/meta/applicator.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/meta/content",
+        include_str!("meta_schemas/draft/2019-09/meta/content.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/meta/core",
+        include_str!("meta_schemas/draft/2019-09/meta/core.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/meta/format",
+        include_str!("meta_schemas/draft/2019-09/meta/format.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/meta/meta-data",
+        include_str!("meta_schemas/draft/2019-09/meta/meta-data.json"),
+    ),
+    (
+        "json-schema.org/draft/2019-09/meta/validation",
+        include_str!("meta_schemas/draft/2019-09/meta/validation.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/schema",
+        include_str!("meta_schemas/draft/2020-12/schema.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/applicator",
+        include_str!("meta_schemas/draft/2020-12/meta/applicator.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/content",
+        include_str!("meta_schemas/draft/2020-12/meta/content.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/core",
+        include_str!("meta_schemas/draft/2020-12/meta/core.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/format-annotation",
+        include_str!("meta_schemas/draft/2020-12/meta/format-annotation.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/format-assertion",
+        include_str!("meta_schemas/draft/2020-12/meta/format-assertion.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/meta-data",
+        include_str!("meta_schemas/draft/2020-12/meta/meta-data.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/unevaluated",
+        include_str!("meta_schemas/draft/2020-12/meta/unevaluated.json"),
+    ),
+    (
+        "json-schema.org/draft/2020-12/meta/validation",
+        include_str!("meta_schemas/draft/2020-12/meta/validation.json"),
+    ),
+];
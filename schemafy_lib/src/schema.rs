@@ -1,7 +1,18 @@
 pub type PositiveInteger = i64;
-pub type PositiveIntegerDefault0 = serde_json::Value;
+pub type PositiveIntegerDefault0 = ::schemafy_core::serde_json::Value;
 pub type SchemaArray = Vec<Schema>;
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[derive(
+    Clone,
+    PartialEq,
+    Debug,
+    ::schemafy_core::serde::Deserialize,
+    ::schemafy_core::serde::Serialize,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+#[serde(crate = "::schemafy_core::serde")]
 #[serde(rename = "simpleTypes")]
 pub enum SimpleTypes {
     #[serde(rename = "array")]
@@ -20,8 +31,23 @@ pub enum SimpleTypes {
     String,
 }
 pub type StringArray = Vec<String>;
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[derive(
+    Clone, PartialEq, Debug, ::schemafy_core::serde::Deserialize, ::schemafy_core::serde::Serialize,
+)]
+#[serde(crate = "::schemafy_core::serde")]
 pub struct Schema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$anchor")]
+    pub anchor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$comment")]
+    pub comment: Option<String>,
+    #[serde(default)]
+    #[serde(rename = "$defs")]
+    pub defs: ::std::collections::BTreeMap<String, Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$id")]
+    pub id_: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "$ref")]
     pub ref_: Option<String>,
@@ -30,10 +56,10 @@ pub struct Schema {
     pub schema: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "additionalItems")]
-    pub additional_items: Option<serde_json::Value>,
+    pub additional_items: Option<::schemafy_core::serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "additionalProperties")]
-    pub additional_properties: Option<serde_json::Value>,
+    pub additional_properties: Option<::schemafy_core::serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "allOf")]
     pub all_of: Option<SchemaArray>,
@@ -41,33 +67,62 @@ pub struct Schema {
     #[serde(rename = "anyOf")]
     pub any_of: Option<SchemaArray>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub default: Option<serde_json::Value>,
+    #[serde(rename = "const")]
+    pub const_: Option<::schemafy_core::serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contains: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contentEncoding")]
+    pub content_encoding: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contentMediaType")]
+    pub content_media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<::schemafy_core::serde_json::Value>,
     #[serde(default)]
     pub definitions: ::std::collections::BTreeMap<String, Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub dependencies: Option<::std::collections::BTreeMap<String, serde_json::Value>>,
+    pub dependencies:
+        Option<::std::collections::BTreeMap<String, ::schemafy_core::serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "dependentRequired")]
+    pub dependent_required: Option<::std::collections::BTreeMap<String, StringArray>>,
+    #[serde(default)]
+    #[serde(rename = "dependentSchemas")]
+    pub dependent_schemas: ::std::collections::BTreeMap<String, Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "else")]
+    pub else_: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "enum")]
-    pub enum_: Option<Vec<serde_json::Value>>,
+    pub enum_: Option<Vec<::schemafy_core::serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "enumNames")]
     pub enum_names: Option<StringArray>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<::schemafy_core::serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "exclusiveMaximum")]
-    pub exclusive_maximum: Option<bool>,
+    pub exclusive_maximum: Option<::schemafy_core::serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "exclusiveMinimum")]
-    pub exclusive_minimum: Option<bool>,
+    pub exclusive_minimum: Option<::schemafy_core::serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "if")]
+    pub if_: Option<Box<Schema>>,
     #[serde(default)]
     #[serde(with = "::schemafy_core::one_or_many")]
     pub items: Vec<Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "maxContains")]
+    pub max_contains: Option<PositiveInteger>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "maxItems")]
     pub max_items: Option<PositiveInteger>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -77,7 +132,10 @@ pub struct Schema {
     #[serde(rename = "maxProperties")]
     pub max_properties: Option<PositiveInteger>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub maximum: Option<f64>,
+    pub maximum: Option<::schemafy_core::serde_json::Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "minContains")]
+    pub min_contains: Option<PositiveInteger>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "minItems")]
     pub min_items: Option<PositiveIntegerDefault0>,
@@ -88,10 +146,10 @@ pub struct Schema {
     #[serde(rename = "minProperties")]
     pub min_properties: Option<PositiveIntegerDefault0>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub minimum: Option<f64>,
+    pub minimum: Option<::schemafy_core::serde_json::Number>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "multipleOf")]
-    pub multiple_of: Option<f64>,
+    pub multiple_of: Option<::schemafy_core::serde_json::Number>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub not: Option<Box<Schema>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -102,17 +160,37 @@ pub struct Schema {
     #[serde(default)]
     #[serde(rename = "patternProperties")]
     pub pattern_properties: ::std::collections::BTreeMap<String, Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "prefixItems")]
+    pub prefix_items: Option<SchemaArray>,
     #[serde(default)]
     pub properties: ::std::collections::BTreeMap<String, Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "propertyNames")]
+    pub property_names: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "readOnly")]
+    pub read_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<StringArray>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub then: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     #[serde(default)]
     #[serde(with = "::schemafy_core::one_or_many")]
     #[serde(rename = "type")]
     pub type_: Vec<SimpleTypes>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "unevaluatedItems")]
+    pub unevaluated_items: Option<::schemafy_core::serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "unevaluatedProperties")]
+    pub unevaluated_properties: Option<::schemafy_core::serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "uniqueItems")]
     pub unique_items: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "writeOnly")]
+    pub write_only: Option<bool>,
 }
@@ -40,3 +40,557 @@ fn test_str_to_ident() {
         Ident::new("thieves_tools", Span::call_site())
     );
 }
+
+#[test]
+#[should_panic(
+    expected = "unsupported JSON Schema dialect `http://example.com/custom-meta-schema#`"
+)]
+fn unsupported_dialect() {
+    let _ = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("tests/unsupported-dialect.json")
+        .build()
+        .generate();
+}
+
+#[test]
+fn draft_override() {
+    let tokens = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("tests/unsupported-dialect.json")
+        .with_draft(schemafy_lib::Draft::Draft7)
+        .build()
+        .generate();
+    assert!(tokens.to_string().contains("pub struct Root"));
+}
+
+#[test]
+fn registry() {
+    let mut registry = schemafy_lib::Registry::new();
+    registry
+        .add_catalog("tests/registry/catalog.json")
+        .expect("Read catalog");
+    let tokens = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("tests/registry/root.json")
+        .with_registry(registry)
+        .build()
+        .generate()
+        .to_string();
+    // Resolved through the catalog
+    assert!(tokens.contains("pub width : Option < Measurement >"));
+    assert!(tokens.contains("pub unit : Option < Unit >"));
+    assert!(tokens.contains("pub enum Unit"));
+    // Resolved to the bundled draft 7 meta-schema
+    assert!(tokens.contains("pub schema : Option < Schema >"));
+    assert!(tokens.contains("pub struct Schema"));
+}
+
+#[test]
+fn unresolved_ref_error() {
+    let err = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("tests/unresolved-ref.json")
+        .build()
+        .try_generate()
+        .unwrap_err();
+    match err {
+        schemafy_lib::Error::UnresolvedRef {
+            ref location,
+            ref reference,
+        } => {
+            assert_eq!(reference, "#/definitions/person");
+            assert!(location
+                .file
+                .as_ref()
+                .unwrap()
+                .ends_with("tests/unresolved-ref.json"));
+            assert_eq!(location.pointer, "/properties/owner");
+            assert_eq!(location.position(), Some((4, 18)));
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn enum_names_error() {
+    let err = schemafy_lib::Generator::builder()
+        .with_input_file("tests/enum-names.json")
+        .build()
+        .try_generate()
+        .unwrap_err();
+    match err {
+        schemafy_lib::Error::EnumNamesLength {
+            ref location,
+            names,
+            values,
+        } => {
+            assert_eq!((names, values), (2, 3));
+            assert_eq!(location.pointer, "/definitions/color");
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn invalid_enum_value_error() {
+    let err = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .build()
+        .try_generate_from_str(r#"{ "definitions": { "point": { "enum": [1, [0, 0]] } } }"#)
+        .unwrap_err();
+    match err {
+        schemafy_lib::Error::InvalidEnumValue {
+            ref location,
+            ref value,
+        } => {
+            assert_eq!(*value, serde_json::json!([0, 0]));
+            assert_eq!(location.pointer, "/definitions/point");
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn invalid_subschema_error() {
+    let err = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .build()
+        .try_generate_from_str(
+            r#"{ "type": "object", "additionalProperties": { "type": "integer", "required": 1 } }"#,
+        )
+        .unwrap_err();
+    match err {
+        schemafy_lib::Error::InvalidSubschema { ref location, .. } => {
+            assert_eq!(location.pointer, "/additionalProperties");
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn invalid_option_errors() {
+    let generate = |builder: schemafy_lib::GeneratorBuilder| {
+        builder
+            .with_root_name_str("Root")
+            .build()
+            .try_generate_from_str(r#"{ "type": "object", "properties": { "a": {} } }"#)
+    };
+    let invalid_option = |result: Result<_, schemafy_lib::Error>| match result {
+        Err(schemafy_lib::Error::InvalidOption { option, .. }) => option,
+        Err(err) => panic!("Unexpected error: {}", err),
+        Ok(_) => panic!("Expected an error"),
+    };
+    let builder = schemafy_lib::Generator::builder;
+    assert_eq!(
+        invalid_option(generate(builder().with_visibility("pub(crate"))),
+        "visibility"
+    );
+    assert_eq!(
+        invalid_option(generate(builder().with_field_visibility("private"))),
+        "field_visibility"
+    );
+    assert_eq!(
+        invalid_option(generate(builder().with_derive("Has h"))),
+        "derive"
+    );
+    assert_eq!(
+        invalid_option(generate(builder().with_serde_path("::my serde"))),
+        "serde_path"
+    );
+    assert_eq!(
+        invalid_option(generate(builder().with_format("email", "Vec<"))),
+        "format"
+    );
+    assert_eq!(
+        invalid_option(generate(builder().with_attribute("non_exhaustive"))),
+        "attribute"
+    );
+    assert_eq!(
+        invalid_option(generate(
+            builder().with_type_attribute("Root", "#[serde(deny_unknown_fields)")
+        )),
+        "attribute"
+    );
+}
+
+#[test]
+fn missing_file_error() {
+    let err = schemafy_lib::Generator::builder()
+        .with_input_file("tests/missing.json")
+        .build()
+        .try_generate()
+        .unwrap_err();
+    assert!(matches!(err, schemafy_lib::Error::Io { .. }));
+    assert!(err.to_string().starts_with("Unable to read `"));
+}
+
+#[test]
+fn dependency_tracking() {
+    let mut registry = schemafy_lib::Registry::new();
+    registry
+        .add_catalog("tests/registry/catalog.json")
+        .expect("Read catalog");
+    let tokens = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("tests/registry/root.json")
+        .with_registry(registry)
+        .with_dependency_tracking()
+        .build()
+        .generate()
+        .to_string();
+    for file in ["root.json", "length.json", "unit.json"] {
+        assert!(
+            tokens.contains(&format!("{}\") ;", file)),
+            "`{}` is not tracked",
+            file
+        );
+    }
+    // Bundled meta-schemas are not files
+    assert_eq!(tokens.matches("include_bytes !").count(), 3);
+}
+
+#[test]
+fn generator_options() {
+    let tokens = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/options.json")
+        .with_derive("Eq")
+        .with_visibility("pub(crate)")
+        .with_map_type("::std::collections::HashMap")
+        .build()
+        .generate()
+        .to_string();
+    let private = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/options.json")
+        .with_field_visibility("")
+        .build()
+        .generate()
+        .to_string();
+    assert!(private.contains("pub struct Root { name : String ,"));
+    assert!(tokens.contains(
+        "# [derive (Clone , PartialEq , Debug , :: schemafy_core :: serde :: Deserialize , \
+         :: schemafy_core :: serde :: Serialize , Eq)] \
+         # [serde (crate = \"::schemafy_core::serde\")]"
+    ));
+    assert!(tokens.contains("pub (crate) struct Root"));
+    assert!(tokens.contains("pub (crate) name : String"));
+    assert!(tokens.contains(":: std :: collections :: HashMap < String , String >"));
+}
+
+/// The derives (by the last segment of their path) and the other attributes of the type `name`
+/// in the generated `code`.
+fn type_attributes(code: &str, name: &str) -> (Vec<String>, Vec<String>) {
+    let file = syn::parse_file(code).unwrap();
+    let attrs = file
+        .items
+        .into_iter()
+        .find_map(|item| match item {
+            syn::Item::Struct(item) if item.ident == name => Some(item.attrs),
+            syn::Item::Enum(item) if item.ident == name => Some(item.attrs),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("No type `{}` in\n{}", name, code));
+    let (derives, others): (Vec<_>, Vec<_>) = attrs
+        .into_iter()
+        .partition(|attr| attr.path.is_ident("derive"));
+    let derives = derives
+        .iter()
+        .flat_map(|attr| {
+            attr.parse_args_with(
+                syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+            )
+            .unwrap()
+        })
+        .map(|path| path.segments.last().unwrap().ident.to_string())
+        .collect();
+    let others = others
+        .iter()
+        .map(|attr| quote::quote!(#attr).to_string())
+        .collect();
+    (derives, others)
+}
+
+#[test]
+fn type_options() {
+    let code = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/options.json")
+        .with_derive("Eq")
+        .without_derive("Debug")
+        .with_attribute("#[non_exhaustive]")
+        .with_type_derive("Root", "Hash")
+        .without_type_derive("Size", "Clone")
+        .with_type_attribute("Size", "#[serde(deny_unknown_fields)]")
+        .build()
+        .try_generate_code()
+        .unwrap();
+    let (derives, attributes) = type_attributes(&code, "Root");
+    for derive in &["Clone", "Eq", "Hash", "PartialOrd", "Ord"] {
+        assert!(derives.iter().any(|d| d == derive), "{:?}", derives);
+    }
+    assert!(!derives.iter().any(|d| d == "Debug"), "{:?}", derives);
+    assert!(attributes.contains(&"# [non_exhaustive]".to_owned()));
+    assert!(!attributes.iter().any(|a| a.contains("deny_unknown_fields")));
+
+    let (derives, attributes) = type_attributes(&code, "Size");
+    assert!(!derives.iter().any(|d| d == "Clone" || d == "Debug"));
+    assert!(derives.iter().any(|d| d == "Eq"), "{:?}", derives);
+    assert!(attributes.contains(&"# [non_exhaustive]".to_owned()));
+    assert!(attributes.contains(&"# [serde (deny_unknown_fields)]".to_owned()));
+}
+
+#[test]
+fn comparison_derives() {
+    let code = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/comparisons.json")
+        .without_type_derive("Parent", "Hash")
+        .build()
+        .try_generate_code()
+        .unwrap();
+    let derives = |name: &str| type_attributes(&code, name).0;
+    let has = |derives: &[String], derive: &str| derives.iter().any(|d| d == derive);
+    // Derived through the cycle between `Tree` and `Parent`
+    for name in &["Tree", "Parent"] {
+        let derives = derives(name);
+        for derive in &["Eq", "PartialOrd", "Ord"] {
+            assert!(has(&derives, derive), "{}: {:?}", name, derives);
+        }
+    }
+    assert!(!has(&derives("Parent"), "Hash"));
+    let point = derives("Point");
+    assert!(has(&point, "PartialOrd"));
+    assert!(!has(&point, "Eq") && !has(&point, "Ord"));
+    assert!(!has(&derives("Root"), "Eq"));
+
+    // Types are told apart by their whole path, not by their name
+    let code = |uuid: &str| {
+        schemafy_lib::Generator::builder()
+            .with_root_name_str("Root")
+            .with_input_file("../tests/formats.json")
+            .with_format_crate(schemafy_lib::FormatCrate::Uuid)
+            .with_format("uuid", uuid)
+            .build()
+            .try_generate_code()
+            .unwrap()
+    };
+    let root = type_attributes(&code("::schemafy_core::uuid::Uuid"), "Root").0;
+    assert!(has(&root, "Ord"), "{:?}", root);
+    let root = type_attributes(&code("::my_uuid::Uuid"), "Root").0;
+    assert!(!has(&root, "Eq"), "{:?}", root);
+}
+
+#[test]
+fn formats() {
+    let tokens = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/formats.json")
+        .with_format("color", "::palette::Srgb")
+        .with_format("uuid", "::my_uuid::Uuid")
+        .build()
+        .generate()
+        .to_string();
+    assert!(tokens.contains("pub color : :: palette :: Srgb ,"));
+    assert!(tokens.contains("pub id : :: my_uuid :: Uuid ,"));
+    assert!(tokens.contains("pub at : String ,"));
+
+    let tokens = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/formats.json")
+        .with_format_crate(schemafy_lib::FormatCrate::Time)
+        .with_format_crate(schemafy_lib::FormatCrate::Uuid)
+        .with_format("uuid", "::my_uuid::Uuid")
+        .build()
+        .generate()
+        .to_string();
+    assert!(tokens.contains("pub created : :: schemafy_core :: Rfc3339 ,"));
+    assert!(tokens.contains("pub at : :: schemafy_core :: Rfc3339Time ,"));
+    assert!(tokens.contains("pub id : :: my_uuid :: Uuid ,"));
+    assert!(tokens.contains("pub ipv_4 : String ,"));
+
+    let err = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/formats.json")
+        .with_format_crate(schemafy_lib::FormatCrate::Chrono)
+        .with_format_crate(schemafy_lib::FormatCrate::Time)
+        .build()
+        .try_generate()
+        .unwrap_err();
+    assert!(
+        matches!(err, schemafy_lib::Error::InvalidOption { ref option, .. } if option == "format_crate"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn number_types() {
+    let generate = |number_type| {
+        schemafy_lib::Generator::builder()
+            .with_root_name_str("Root")
+            .with_input_file("../tests/numbers.json")
+            .with_format("kilograms", "::uom::si::f64::Mass")
+            .with_number_type(number_type)
+            .build()
+            .generate()
+            .to_string()
+    };
+    let tokens = generate(schemafy_lib::NumberType::F64);
+    assert!(tokens.contains("pub price : :: schemafy_core :: serde_json :: Number ,"));
+    assert!(tokens.contains("pub ratio : f64 ,"));
+    assert!(tokens.contains("pub weight : Option < :: uom :: si :: f64 :: Mass >"));
+    assert!(!tokens.contains("require_decimal"));
+
+    let tokens = generate(schemafy_lib::NumberType::Number);
+    assert!(tokens.contains("pub ratio : :: schemafy_core :: serde_json :: Number ,"));
+
+    let tokens = generate(schemafy_lib::NumberType::Decimal);
+    assert!(tokens.starts_with(":: schemafy_core :: require_decimal ! () ;"));
+    assert!(tokens.contains("pub price : :: schemafy_core :: Decimal ,"));
+    assert!(tokens.contains("pub ratio : :: schemafy_core :: Decimal ,"));
+}
+
+#[test]
+fn formatted_code() {
+    let code = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file("../tests/options.json")
+        .build()
+        .try_generate_code()
+        .unwrap();
+    assert!(code.contains("pub struct Root {\n    pub name: String,\n"));
+    assert!(code.contains("#[serde(rename = \"small\")]\n    Small,\n"));
+}
+
+#[test]
+fn inline_schema() {
+    let generator = schemafy_lib::Generator::builder()
+        .with_root_name_str("Root")
+        .build();
+    // Relative references are resolved against the crate root
+    let tokens = generator
+        .try_generate_from_str(
+            r#"{
+                "type": "object",
+                "properties": {
+                    "length": { "$ref": "tests/registry/vendor/units/length.json" }
+                }
+            }"#,
+        )
+        .unwrap()
+        .to_string();
+    assert!(tokens.contains("pub struct Root"));
+    assert!(tokens.contains("pub length : Option < Length >"));
+
+    let schema: schemafy_lib::Schema = serde_json::from_str(
+        r#"{ "type": "object", "properties": { "a": { "type": "string" } } }"#,
+    )
+    .unwrap();
+    let tokens = generator
+        .try_generate_from_schema(&schema)
+        .unwrap()
+        .to_string();
+    assert!(tokens.contains("pub a : Option < String >"));
+
+    // The description of an integer enum documents the enum itself
+    let tokens = generator
+        .try_generate_from_str(
+            r##"{
+                "properties": { "level": { "$ref": "#/definitions/level" } },
+                "definitions": { "level": { "description": "A level", "enum": [1, 2] } }
+            }"##,
+        )
+        .unwrap()
+        .to_string();
+    assert!(!tokens.contains("mod "), "{}", tokens);
+    assert!(
+        tokens.contains("# [doc = \" A level\"] # [derive (Clone"),
+        "{}",
+        tokens
+    );
+
+    let err = generator.try_generate_from_str("{ \"type\": ").unwrap_err();
+    assert!(matches!(err, schemafy_lib::Error::Json { .. }));
+}
+
+#[test]
+fn build_modules() {
+    let out_dir = std::env::temp_dir().join(format!("schemafy-build-{}", std::process::id()));
+    let build = schemafy_lib::build::Build::new()
+        .with_schema("tests/build/**/*.json")
+        .with_out_dir(&out_dir)
+        .with_generator(|builder| builder.with_derive("Default"));
+    let modules = build.generate().unwrap();
+    // `color.json` is not matched, but both shapes refer to it
+    assert_eq!(
+        modules,
+        vec![
+            out_dir.join("point.rs"),
+            out_dir.join("circle.rs"),
+            out_dir.join("square.rs"),
+            out_dir.join("color.rs"),
+        ]
+    );
+    let circle = std::fs::read_to_string(out_dir.join("circle.rs")).unwrap();
+    assert!(circle.contains("pub struct Circle"));
+    assert!(circle.contains("pub center: Point"));
+    assert!(circle.contains("pub color: Option<Color>"));
+
+    // Every type is generated in one module only
+    let mut types = Vec::new();
+    for module in &modules {
+        let file = syn::parse_file(&std::fs::read_to_string(module).unwrap()).unwrap();
+        for item in file.items {
+            match item {
+                syn::Item::Struct(item) => types.push(item.ident.to_string()),
+                syn::Item::Enum(item) => types.push(item.ident.to_string()),
+                syn::Item::Type(item) => types.push(item.ident.to_string()),
+                _ => (),
+            }
+        }
+    }
+    types.sort();
+    assert_eq!(types, ["Circle", "Color", "Point", "Square"]);
+
+    // Nothing changed, so the module is not written again
+    let modified = || {
+        std::fs::metadata(out_dir.join("circle.rs"))
+            .unwrap()
+            .modified()
+            .unwrap()
+    };
+    let before = modified();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    build.generate().unwrap();
+    assert_eq!(modified(), before);
+
+    // A module which does not match what would be generated is replaced
+    std::fs::write(out_dir.join("circle.rs"), "// stale").unwrap();
+    build.generate().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(out_dir.join("circle.rs")).unwrap(),
+        circle
+    );
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn build_module_name_collision() {
+    let out_dir =
+        std::env::temp_dir().join(format!("schemafy-build-collision-{}", std::process::id()));
+    let err = schemafy_lib::build::Build::new()
+        .with_schema("tests/build-collision/**/*.json")
+        .with_out_dir(&out_dir)
+        .generate()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("the module `common` is already generated"),
+        "{}",
+        err
+    );
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
@@ -1,7 +1,6 @@
 //! Generate test cases from the JSON Schema Test Suite.
 
 use inflector::Inflector;
-use serde::{Deserialize, Serialize};
 use std::{error::Error, ffi::OsStr, fs, path::PathBuf, process::Command};
 
 // Each test has a description, schema, and a list of tests. Each of
@@ -64,9 +63,6 @@ fn main() -> Result<(), Box<dyn Error>> {
             test_file.push_str(&format!(
                 r#"
 mod _{}_{} {{
-    #[allow(unused_imports)]
-    use serde::{{Deserialize, Serialize}};
-
     {}
 "#,
                 i,
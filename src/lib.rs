@@ -4,7 +4,7 @@
 // #![doc(include = "../README.md")]
 
 //! This is a Rust crate which can take a [json schema (draft
-//! 4)](http://json-schema.org/) and generate Rust types which are
+//! 4, 6, 7, 2019-09 or 2020-12)](http://json-schema.org/) and generate Rust types which are
 //! serializable with [serde](https://serde.rs/). No checking such as
 //! `min_value` are done but instead only the structure of the schema
 //! is followed as closely as possible.
@@ -26,12 +26,6 @@
 //! procedural macro.
 //!
 //! ```rust
-//! extern crate serde;
-//! extern crate schemafy_core;
-//! extern crate serde_json;
-//!
-//! use serde::{Serialize, Deserialize};
-//!
 //! schemafy::schemafy!(
 //!     "tests/nested.json"
 //! );
@@ -51,60 +45,267 @@
 
 /// Generate Rust types from a JSON schema.
 ///
-/// If the `root` parameter is supplied, then a type will be
-/// generated from the root of the schema.
+/// The macro takes the path to the schema file, relative to the crate
+/// root, or the schema itself as `json = r#"{ ... }"#`, and optionally some
+/// `key: value` options (separated by commas):
 ///
-/// ```rust
-/// extern crate serde;
-/// extern crate schemafy_core;
-/// extern crate serde_json;
+/// - `root: Name` generates a type named `Name` for the root of the schema.
+/// - `derive: [Eq, Hash]` adds derives to every generated type.
+/// - `visibility: pub(crate)` sets the visibility of the generated types
+///   and their fields (`pub` by default).
+/// - `field_visibility: pub(self)` sets the visibility of the fields of
+///   the generated structs, here making them private.
+/// - `schemafy_path: ::my_crate::schemafy_core` is the path to
+///   `schemafy_core`, if it is re-exported.
+/// - `serde_path: ::serde` is the path to the `serde` crate used by the
+///   generated code (the one re-exported by `schemafy_core` by default).
+/// - `map: IndexMap` is the map type used for objects without fixed
+///   properties (`BTreeMap` by default).
+/// - `strict: true` rejects unknown fields in every struct, unless the
+///   schema allows them explicitly.
+/// - `narrow_integers: true` generates integers as the narrowest type
+///   (such as `u8`) which fits their bounds and `format`, instead of `i64`.
+/// - `number_type: Number` or `number_type: Decimal` generates numbers as a
+///   `serde_json::Number` or a `schemafy_core::Decimal` instead of an `f64`.
+///   `Decimal` keeps their exact value but needs the `decimal` feature of
+///   `schemafy_core`, which enables the `arbitrary_precision` feature of
+///   `serde_json` for the whole build. A `Number` keeps integers exactly but
+///   other numbers only with `arbitrary_precision`. Numbers with the format
+///   `decimal` are a `Decimal` with `number_type: Decimal`, or else a
+///   `Number`.
+/// - `format_crates: [chrono, uuid]` generates strings with a `format` as
+///   the types of these crates (`chrono`, `time`, `uuid`, `url`, `email` or
+///   `net`, which is `std::net`), `date-time` as `chrono::DateTime` for
+///   instance. Enable the features of the same name on `schemafy_core`,
+///   except for `net`.
 ///
-/// use serde::{Serialize, Deserialize};
+/// The generated code refers to `serde` through `schemafy_core`, so
+/// the macro can be used in any module without importing anything.
 ///
+/// ```rust
 /// schemafy::schemafy!(
 ///     root: MyRoot // Optional name for the root type (if one exists)
 ///     "tests/nested.json"
 /// );
 ///
+/// schemafy::schemafy!(
+///     root: Point,
+///     json = r#"{
+///         "type": "object",
+///         "properties": { "x": { "type": "number" }, "y": { "type": "number" } },
+///         "required": ["x", "y"]
+///     }"#
+/// );
+///
 /// fn main() -> Result<(), Box<dyn std::error::Error>> {
 ///     let nested: Defnested = serde_json::from_str(r#"{ "append": "abc" }"#)?;
 ///     assert_eq!(nested.append, Some("abc".to_string()));
+///     let point: Point = serde_json::from_str(r#"{ "x": 1, "y": 2 }"#)?;
+///     assert_eq!(point.x, 1.0);
 ///     Ok(())
 /// }
 /// ```
 #[proc_macro]
 pub fn schemafy(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let def = syn::parse_macro_input!(tokens as Def);
-    let root_name = def.root;
-    let input_file = def.input_file.value();
-    schemafy_lib::Generator::builder()
-        .with_root_name(root_name)
-        .with_input_file(&input_file)
-        .build()
-        .generate()
-        .into()
+    let input_file = match def.input {
+        Input::File(ref file) => Some(file.value()),
+        Input::Json(_) => None,
+    };
+    let schemafy_path = def
+        .schemafy_path
+        .as_ref()
+        .map(|path| format!("{}::", path_string(path)));
+    let mut builder = schemafy_lib::Generator::builder()
+        .with_root_name(def.root)
+        .with_strict(def.strict)
+        .with_narrow_integers(def.narrow_integers)
+        .with_number_type(def.number_type)
+        .with_dependency_tracking();
+    if let Some(ref input_file) = input_file {
+        builder = builder.with_input_file(input_file);
+    }
+    if let Some(ref schemafy_path) = schemafy_path {
+        builder = builder.with_schemafy_path(schemafy_path);
+    }
+    let serde_path = def.serde_path.as_ref().map(path_string);
+    if let Some(ref serde_path) = serde_path {
+        builder = builder.with_serde_path(serde_path);
+    }
+    for &format_crate in &def.format_crates {
+        builder = builder.with_format_crate(format_crate);
+    }
+    for derive in &def.derives {
+        builder = builder.with_derive(path_string(derive));
+    }
+    if let Some(ref visibility) = def.visibility {
+        builder = builder.with_visibility(quote::quote!(#visibility).to_string());
+    }
+    if let Some(ref visibility) = def.field_visibility {
+        builder = builder.with_field_visibility(quote::quote!(#visibility).to_string());
+    }
+    if let Some(ref map) = def.map {
+        builder = builder.with_map_type(path_string(map));
+    }
+    let generator = builder.build();
+    let (result, literal) = match def.input {
+        Input::File(ref file) => (generator.try_generate(), file),
+        Input::Json(ref json) => (generator.try_generate_from_str(&json.value()), json),
+    };
+    match result {
+        Ok(tokens) => tokens.into(),
+        Err(err) => syn::Error::new(literal.span(), error_message(&err))
+            .to_compile_error()
+            .into(),
+    }
+}
+
+/// The message for `err`, with the line and column of the error in the schema file (JSON syntax
+/// errors already include them).
+fn error_message(err: &schemafy_lib::Error) -> String {
+    match err.position() {
+        Some((line, column)) if !matches!(err, schemafy_lib::Error::Json { .. }) => {
+            format!("{} (line {}, column {})", err, line, column)
+        }
+        _ => err.to_string(),
+    }
+}
+
+fn path_string(path: &syn::Path) -> String {
+    quote::quote!(#path).to_string().replace(' ', "")
+}
+
+/// Where the schema comes from.
+enum Input {
+    /// The path to a schema file.
+    File(syn::LitStr),
+    /// The schema itself.
+    Json(syn::LitStr),
 }
 
 struct Def {
     root: Option<String>,
-    input_file: syn::LitStr,
+    input: Input,
+    derives: Vec<syn::Path>,
+    visibility: Option<syn::Visibility>,
+    field_visibility: Option<syn::Visibility>,
+    schemafy_path: Option<syn::Path>,
+    serde_path: Option<syn::Path>,
+    map: Option<syn::Path>,
+    strict: bool,
+    narrow_integers: bool,
+    number_type: schemafy_lib::NumberType,
+    format_crates: Vec<schemafy_lib::FormatCrate>,
 }
 
 impl syn::parse::Parse for Def {
     fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
-        let root = if input.peek(syn::Ident) {
-            let root_ident: syn::Ident = input.parse()?;
-            if root_ident != "root" {
-                return Err(syn::Error::new(root_ident.span(), "Expected `root`"));
+        let mut root = None;
+        let mut schema_input = None;
+        let mut derives = Vec::new();
+        let mut visibility = None;
+        let mut field_visibility = None;
+        let mut schemafy_path = None;
+        let mut serde_path = None;
+        let mut map = None;
+        let mut strict = false;
+        let mut narrow_integers = false;
+        let mut number_type = schemafy_lib::NumberType::F64;
+        let mut format_crates = Vec::new();
+        while !input.is_empty() {
+            if input.peek(syn::LitStr) {
+                let file: syn::LitStr = input.parse()?;
+                if schema_input.is_some() {
+                    return Err(syn::Error::new(file.span(), "Expected a single schema"));
+                }
+                schema_input = Some(Input::File(file));
+            } else {
+                let key: syn::Ident = input.parse()?;
+                if input.peek(syn::Token![=]) {
+                    input.parse::<syn::Token![=]>()?;
+                } else {
+                    input.parse::<syn::Token![:]>()?;
+                }
+                match &*key.to_string() {
+                    "json" => {
+                        let json: syn::LitStr = input.parse()?;
+                        if schema_input.is_some() {
+                            return Err(syn::Error::new(json.span(), "Expected a single schema"));
+                        }
+                        schema_input = Some(Input::Json(json));
+                    }
+                    "root" => root = Some(input.parse::<syn::Ident>()?.to_string()),
+                    "derive" => {
+                        let content;
+                        syn::bracketed!(content in input);
+                        derives.extend(
+                            content.parse_terminated::<_, syn::Token![,]>(syn::Path::parse)?,
+                        );
+                    }
+                    "visibility" => visibility = Some(input.parse()?),
+                    "field_visibility" => field_visibility = Some(input.parse()?),
+                    "schemafy_path" => schemafy_path = Some(input.parse()?),
+                    "serde_path" => serde_path = Some(input.parse()?),
+                    "map" => map = Some(input.parse()?),
+                    "strict" => strict = input.parse::<syn::LitBool>()?.value,
+                    "narrow_integers" => narrow_integers = input.parse::<syn::LitBool>()?.value,
+                    "number_type" => {
+                        let typ: syn::Ident = input.parse()?;
+                        number_type = match &typ.to_string()[..] {
+                            "f64" => schemafy_lib::NumberType::F64,
+                            "Number" => schemafy_lib::NumberType::Number,
+                            "Decimal" => schemafy_lib::NumberType::Decimal,
+                            _ => {
+                                return Err(syn::Error::new(
+                                    typ.span(),
+                                    "Expected `f64`, `Number` or `Decimal`",
+                                ))
+                            }
+                        };
+                    }
+                    "format_crates" => {
+                        let content;
+                        syn::bracketed!(content in input);
+                        let names =
+                            content.parse_terminated::<_, syn::Token![,]>(syn::Ident::parse)?;
+                        for name in names {
+                            let format_crate = name
+                                .to_string()
+                                .parse()
+                                .map_err(|err: String| syn::Error::new(name.span(), err))?;
+                            format_crates.push(format_crate);
+                        }
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            "Expected `root`, `json`, `derive`, `visibility`, `field_visibility`, \
+                             `schemafy_path`, `serde_path`, `map`, `strict`, `narrow_integers`, \
+                             `number_type` or `format_crates`",
+                        ))
+                    }
+                }
+            }
+            if !input.is_empty() && input.peek(syn::Token![,]) {
+                input.parse::<syn::Token![,]>()?;
             }
-            input.parse::<syn::Token![:]>()?;
-            Some(input.parse::<syn::Ident>()?.to_string())
-        } else {
-            None
-        };
+        }
+        let input = schema_input.ok_or_else(|| {
+            input.error("Expected the path to a JSON schema file or `json = \"...\"`")
+        })?;
         Ok(Def {
             root,
-            input_file: input.parse()?,
+            input,
+            derives,
+            visibility,
+            field_visibility,
+            schemafy_path,
+            serde_path,
+            map,
+            strict,
+            narrow_integers,
+            number_type,
+            format_crates,
         })
     }
 }
@@ -1,9 +1,8 @@
-use std::io::copy;
+use std::io::Write;
 use std::path::Path;
-use std::process::{Command, Stdio};
 
-use anyhow::{anyhow, bail, Context, Result};
-use schemafy_lib::Generator;
+use anyhow::{anyhow, Context, Result};
+use schemafy_lib::{FormatCrate, Generator, NumberType, Registry};
 use structopt::StructOpt;
 use tempfile::NamedTempFile;
 
@@ -16,6 +15,45 @@ struct Opts {
     /// Output file [default: stdout]
     #[structopt(short, long, value_name = "PATH")]
     output: Option<String>,
+    /// Read schemas with URIs starting with PREFIX from DIR
+    #[structopt(long = "map", value_name = "PREFIX=DIR", number_of_values = 1)]
+    mappings: Vec<String>,
+    /// Read URI mappings from a catalog file
+    #[structopt(long = "catalog", value_name = "PATH", number_of_values = 1)]
+    catalogs: Vec<String>,
+    /// Add a derive to every type, or to type NAME only with NAME=DERIVE
+    #[structopt(long = "derive", value_name = "DERIVE", number_of_values = 1)]
+    derives: Vec<String>,
+    /// Remove a derive from every type, or from type NAME only with NAME=DERIVE
+    #[structopt(long = "no-derive", value_name = "DERIVE", number_of_values = 1)]
+    removed_derives: Vec<String>,
+    /// Add an attribute such as `#[non_exhaustive]` to every type, or to type NAME only with NAME=ATTRIBUTE
+    #[structopt(long = "attribute", value_name = "ATTRIBUTE", number_of_values = 1)]
+    attributes: Vec<String>,
+    /// Visibility of the generated types, such as `pub(crate)`
+    #[structopt(long, value_name = "VISIBILITY", default_value = "pub")]
+    visibility: String,
+    /// Visibility of the fields of the generated structs, `pub(self)` for private fields [default: the visibility of the types]
+    #[structopt(long, value_name = "VISIBILITY")]
+    field_visibility: Option<String>,
+    /// Generate integers as the narrowest type which fits their bounds, instead of i64
+    #[structopt(long)]
+    narrow_integers: bool,
+    /// Type of numbers: f64, number (serde_json::Number) or decimal (schemafy_core::Decimal, which schemafy_core needs the decimal feature of)
+    #[structopt(long, value_name = "TYPE", default_value = "f64", possible_values = &["f64", "number", "decimal"])]
+    number_type: String,
+    /// Generate strings with a format as the types of CRATE, which schemafy_core needs the feature of
+    #[structopt(long = "format-crate", value_name = "CRATE", number_of_values = 1, possible_values = &["chrono", "email", "net", "time", "url", "uuid"])]
+    format_crates: Vec<FormatCrate>,
+    /// Use TYPE for strings with FORMAT
+    #[structopt(long = "format", value_name = "FORMAT=TYPE", number_of_values = 1)]
+    formats: Vec<String>,
+    /// Path to the serde crate used by the generated code [default: the one re-exported by schemafy_core]
+    #[structopt(long, value_name = "PATH")]
+    serde_path: Option<String>,
+    /// Format the output with the rustfmt in PATH instead of the built-in formatter
+    #[structopt(long)]
+    rustfmt: bool,
     /// JSON schema file
     schema_path: String,
 }
@@ -23,50 +61,84 @@ struct Opts {
 pub fn main() -> Result<()> {
     let opts = Opts::from_args();
 
+    let mut registry = Registry::new();
+    for catalog in &opts.catalogs {
+        registry
+            .add_catalog(catalog)
+            .with_context(|| format!("reading catalog `{}`", catalog))?;
+    }
+    for mapping in &opts.mappings {
+        let (prefix, directory) = mapping
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected PREFIX=DIR, got `{}`", mapping))?;
+        registry.insert(prefix, directory);
+    }
+
     // generate the Rust code
-    let mut generated_file = NamedTempFile::new()?;
-    Generator::builder()
+    let mut builder = Generator::builder()
         .with_root_name_str(&opts.root)
         .with_input_file(&opts.schema_path)
-        .build()
-        .generate_to_file(
-            &generated_file
-                .path()
-                .to_str()
-                .ok_or_else(|| anyhow!("converting output path"))?,
-        )?;
-
-    // run it through rustfmt and write it out
-    let (output_file, output_path) = NamedTempFile::new_in(
-        opts.output
-            .as_ref()
-            .map(|p| Path::new(p).parent())
-            .flatten()
-            .unwrap_or(&std::env::temp_dir()),
-    )
-    .context("creating temporary output file")?
-    .into_parts();
-    let mut formatter = Command::new("rustfmt")
-        .args(&["--edition", "2018"])
-        .stdin(Stdio::piped())
-        .stdout(
-            opts.output
-                .as_ref()
-                .and(Some(output_file.into()))
-                .unwrap_or(Stdio::inherit()),
-        )
-        .spawn()
-        .context("running rustfmt")?;
-    copy(
-        generated_file.as_file_mut(),
-        formatter.stdin.as_mut().expect("stdin"),
-    )?;
-    let result = formatter.wait()?;
-    if !result.success() {
-        bail!("rustfmt failed");
+        .with_registry(registry)
+        .with_visibility(&opts.visibility)
+        .with_narrow_integers(opts.narrow_integers)
+        .with_number_type(match &opts.number_type[..] {
+            "number" => NumberType::Number,
+            "decimal" => NumberType::Decimal,
+            _ => NumberType::F64,
+        })
+        .with_rustfmt(opts.rustfmt);
+    if let Some(ref visibility) = opts.field_visibility {
+        builder = builder.with_field_visibility(visibility);
+    }
+    if let Some(ref serde_path) = opts.serde_path {
+        builder = builder.with_serde_path(serde_path);
+    }
+    for &format_crate in &opts.format_crates {
+        builder = builder.with_format_crate(format_crate);
     }
-    if let Some(path) = &opts.output {
-        output_path.persist(path)?;
+    for format in &opts.formats {
+        let (format, typ) = format
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected FORMAT=TYPE, got `{}`", format))?;
+        builder = builder.with_format(format, typ);
+    }
+    for derive in &opts.derives {
+        builder = match derive.split_once('=') {
+            Some((name, derive)) => builder.with_type_derive(name, derive),
+            None => builder.with_derive(derive),
+        };
+    }
+    for derive in &opts.removed_derives {
+        builder = match derive.split_once('=') {
+            Some((name, derive)) => builder.without_type_derive(name, derive),
+            None => builder.without_derive(derive),
+        };
+    }
+    for attribute in &opts.attributes {
+        // Attributes may contain `=` themselves
+        builder = match attribute.split_once('=') {
+            Some((name, attribute)) if !name.trim_start().starts_with('#') => {
+                builder.with_type_attribute(name, attribute)
+            }
+            _ => builder.with_attribute(attribute),
+        };
+    }
+    let code = builder.build().try_generate_code()?;
+
+    // write it out, replacing the output file only once the code is complete
+    match &opts.output {
+        Some(path) => {
+            let mut output_file = NamedTempFile::new_in(
+                Path::new(path)
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .unwrap_or_else(|| Path::new(".")),
+            )
+            .context("creating temporary output file")?;
+            output_file.write_all(code.as_bytes())?;
+            output_file.persist(path)?;
+        }
+        None => std::io::stdout().write_all(code.as_bytes())?,
     }
 
     Ok(())
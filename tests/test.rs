@@ -1,6 +1,3 @@
-use serde_derive::{Deserialize, Serialize};
-use serde_repr::{Deserialize_repr, Serialize_repr};
-
 schemafy::schemafy!(
     root: Schema
     "schemafy_lib/src/schema.json"
@@ -119,6 +116,7 @@ schemafy::schemafy!(
 );
 
 #[test]
+#[allow(clippy::get_first)]
 fn root_array() {
     let a = RootArray::default();
     let _: Option<&RootArrayItem> = a.get(0);
@@ -135,6 +133,84 @@ fn enum_names_int() {
     assert_eq!(serde_json::to_string(&EnumNamesInt::B).unwrap(), "2");
 }
 
+schemafy::schemafy!(
+    root: Comparisons
+    "tests/comparisons.json"
+);
+
+#[test]
+fn comparison_derives() {
+    let tree = Tree {
+        children: None,
+        name: "a".into(),
+        parent: Some(Parent {
+            depth: Some(1),
+            siblings: Some(vec![]),
+        }),
+    };
+    let set = std::iter::once(tree.clone()).collect::<std::collections::HashSet<_>>();
+    assert!(set.contains(&tree));
+    let set = std::iter::once(tree.clone()).collect::<std::collections::BTreeSet<_>>();
+    assert!(set.contains(&tree));
+    assert!(Point { x: 1.0, y: 2.0 } < Point { x: 2.0, y: 0.0 });
+}
+
+#[test]
+fn in_function_body() {
+    // Nothing needs to be in scope, and the `serde` crate may be given explicitly
+    schemafy::schemafy!(
+        root: Local,
+        serde_path: ::serde,
+        "tests/enum-names-int.json"
+    );
+    assert_eq!(serde_json::to_string(&Local::B).unwrap(), "2");
+    assert_eq!(serde_json::from_str::<Local>("1").unwrap(), Local::A);
+}
+
+mod call_site_derives {
+    use std::marker::Copy as Duplicate;
+
+    // Derives only need to be in scope where the macro is invoked
+    schemafy::schemafy!(
+        root: Level,
+        derive: [Duplicate],
+        "tests/enum-names-int.json"
+    );
+
+    #[test]
+    fn call_site_derives() {
+        let level = Level::B;
+        let copy = level;
+        assert_eq!(level, copy);
+        assert_eq!(serde_json::to_string(&level).unwrap(), "2");
+        let err = serde_json::from_str::<Level>("3").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value: integer `3`, expected one of 1, 2"
+        );
+    }
+}
+
+mod relative_paths {
+    mod reexports {
+        pub use ::schemafy_core::*;
+    }
+
+    schemafy::schemafy!(
+        root: Relative,
+        schemafy_path: self::reexports,
+        serde_path: super::relative_paths::reexports::serde,
+        "tests/enum-names-int.json"
+    );
+
+    #[test]
+    fn relative_paths() {
+        // The integer enum refers to `serde` through the relative path
+        assert_eq!(serde_json::to_string(&Relative::B).unwrap(), "2");
+        assert_eq!(serde_json::from_str::<Relative>("1").unwrap(), Relative::A);
+    }
+}
+
 schemafy::schemafy!(
     root: EnumNamesStr
     "tests/enum-names-str.json"
@@ -198,3 +274,499 @@ fn unknown_fields() {
     // non-empty struct with additionalProperties unspecified
     serde_json::from_str::<ArrayType>(r#"{"required": [], "zzz": 5}"#).unwrap();
 }
+
+schemafy::schemafy!(
+    root: Draft6
+    "tests/draft6.json"
+);
+
+#[test]
+fn draft6_keywords() {
+    let o: Draft6 = serde_json::from_str(
+        r#"{"kind": "circle", "version": 2, "radius": 1.5, "tags": [1, "a"], "labels": {"a": "b"}}"#,
+    )
+    .unwrap();
+    assert_eq!(o.kind, Draft6Kind::Circle);
+    assert_eq!(o.version, Some(Draft6Version::V2));
+    let _: Option<Vec<serde_json::Value>> = o.tags;
+    let _: Option<::std::collections::BTreeMap<String, String>> = o.labels;
+    serde_json::from_str::<Draft6>(r#"{"kind": "square", "radius": 1.5}"#).unwrap_err();
+    serde_json::from_str::<Draft6>(r#"{"kind": "circle", "version": 3, "radius": 1.5}"#)
+        .unwrap_err();
+
+    let o: Draft6 =
+        serde_json::from_str(r#"{"kind": "circle", "radius": 1.5, "strict": true, "scale": 0.5}"#)
+            .unwrap();
+    assert_eq!(o.strict, Some(Draft6Strict::True));
+    assert_eq!(o.scale, Some(Draft6Scale::V0Point5));
+    serde_json::from_str::<Draft6>(r#"{"kind": "circle", "radius": 1.5, "strict": false}"#)
+        .unwrap_err();
+    serde_json::from_str::<Draft6>(r#"{"kind": "circle", "radius": 1.5, "scale": 1.5}"#)
+        .unwrap_err();
+    assert_eq!(serde_json::to_string(&Draft6Version::V2).unwrap(), "2");
+    assert_eq!(serde_json::to_string(&Draft6Strict::True).unwrap(), "true");
+    assert_eq!(serde_json::to_string(&Unit::Cm).unwrap(), r#""cm""#);
+}
+
+schemafy::schemafy!(
+    root: Draft7
+    "tests/draft7.json"
+);
+
+#[test]
+fn draft7_conditional() {
+    let us: Draft7 =
+        serde_json::from_str(r#"{"country": "US", "postal_code": "12345-6789"}"#).unwrap();
+    match us {
+        Draft7::Then(then) => {
+            assert_eq!(then.country, Draft7ThenCountry::Us);
+            assert_eq!(then.postal_code, "12345-6789");
+        }
+        Draft7::Else(_) => panic!("Expected the `then` shape"),
+    }
+    let nl: Draft7 = serde_json::from_str(r#"{"country": "NL", "postal_code": 1234}"#).unwrap();
+    assert_eq!(
+        nl,
+        Draft7::Else(Draft7Else {
+            country: "NL".into(),
+            id: None,
+            photo: None,
+            postal_code: Some(1234),
+        })
+    );
+    // A US address matches `if`, so it is rejected unless it also satisfies `then`, even
+    // though it would fit the `else` shape
+    serde_json::from_str::<Draft7>(r#"{"country": "US"}"#).unwrap_err();
+    serde_json::from_str::<Draft7>(r#"{"country": "US", "postal_code": 12345}"#).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: Draft201909
+    "tests/draft2019-09.json"
+);
+
+#[test]
+fn draft2019_09_refs() {
+    let o: Draft201909 = serde_json::from_str(
+        r#"{
+            "home": {"street": "a"},
+            "work": {"street": "b"},
+            "office": {"street": "c", "company": "d"}
+        }"#,
+    )
+    .unwrap();
+    let _: Option<Address> = o.home;
+    let _: Option<Address> = o.work;
+    let office: Draft201909Office = o.office.unwrap();
+    assert_eq!(office.street, "c");
+    assert_eq!(office.company, "d");
+
+    serde_json::from_str::<Draft201909Office>(r#"{"street": "c", "company": "d", "zzz": 1}"#)
+        .unwrap_err();
+    serde_json::from_str::<Strict>(r#"{"zzz": 1}"#).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: Draft202012
+    "tests/draft2020-12.json"
+);
+
+#[test]
+fn draft2020_12_prefix_items() {
+    let o: Draft202012 = serde_json::from_str(
+        r#"{"point": [1.0, 2.5], "command": ["run", {"flag": true}], "tags": ["a"]}"#,
+    )
+    .unwrap();
+    assert_eq!(o.point, schemafy_core::TupleRest((1.0, 2.5), vec![]));
+    assert_eq!(
+        o.command,
+        Some(schemafy_core::TupleRest(
+            ("run".to_string(),),
+            vec![Draft202012ItemCommand { flag: Some(true) }]
+        ))
+    );
+    assert_eq!(o.tags, Some(vec!["a".to_string()]));
+    assert_eq!(o.label, None::<(String,)>);
+    assert_eq!(
+        serde_json::to_string(&o.command).unwrap(),
+        r#"["run",{"flag":true}]"#
+    );
+
+    // Without `items` any elements may follow the prefix
+    let o: Draft202012 = serde_json::from_str(r#"{"point": [1.0, 2.5, "z"]}"#).unwrap();
+    assert_eq!(o.point.1, vec![serde_json::json!("z")]);
+
+    // Too long for a tuple
+    let o: Draft202012 = serde_json::from_str(
+        r#"{"point": [1.0, 2.5], "long": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13]}"#,
+    )
+    .unwrap();
+    let _: Option<Vec<serde_json::Value>> = o.long;
+}
+
+schemafy::schemafy!(
+    root: PlainNameId
+    "tests/plain-name-id.json"
+);
+
+#[test]
+fn plain_name_id() {
+    let o: PlainNameId = serde_json::from_str(r#"{"home": {"street": "a"}}"#).unwrap();
+    let _: Option<Residence> = o.home;
+}
+
+schemafy::schemafy!(
+    root: BooleanSchemas
+    "tests/boolean-schemas.json"
+);
+
+#[test]
+fn boolean_schemas() {
+    let o: BooleanSchemas = serde_json::from_str(r#"{"anything": 1, "list": [1, "a"]}"#).unwrap();
+    assert_eq!(o.anything, Some(serde_json::json!(1)));
+    assert_eq!(
+        o.list,
+        Some(vec![serde_json::json!(1), serde_json::json!("a")])
+    );
+    let _: Option<schemafy_core::Never> = o.forbidden;
+    let _: Option<Nothing> = o.nothing;
+    let _: Something = serde_json::Value::Null;
+
+    serde_json::from_str::<BooleanSchemas>(r#"{"forbidden": 1}"#).unwrap_err();
+    serde_json::from_str::<BooleanSchemas>(r#"{"nothing": 1}"#).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: ExternalRefs
+    "tests/external-refs/root.json"
+);
+
+#[test]
+fn external_refs() {
+    let o: ExternalRefs = serde_json::from_str(
+        r#"{
+            "shipping": {"street": "a"},
+            "owner": {"name": "b", "address": {"street": "c"}, "tier": "gold"},
+            "backup": {"name": "d"}
+        }"#,
+    )
+    .unwrap();
+    let shipping: PostalAddress = o.shipping.unwrap();
+    assert_eq!(shipping.street, "a");
+    let owner: Contact = o.owner.unwrap();
+    assert_eq!(owner.name, "b");
+    assert_eq!(owner.address.unwrap().street, "c");
+    assert_eq!(owner.tier, Some(Tier::Gold));
+    let _: Option<Contact> = o.backup;
+    let _: Unused = 1;
+}
+
+mod clashing_names {
+    schemafy::schemafy!(
+        root: ClashingNames
+        "tests/clashing-names/root.json"
+    );
+
+    #[test]
+    fn clashing_names() {
+        let o: ClashingNames = serde_json::from_str(
+            r#"{"first": "a", "second": 1, "a": {"id": "b", "name": 2}, "b": {"id": 3}}"#,
+        )
+        .unwrap();
+        // The names of other documents are qualified with their paths where they clash
+        let _: Option<Id> = o.first;
+        let _: Option<CommonId> = o.second;
+        let a: Common = o.a.unwrap();
+        assert_eq!(a.id, Some("b".to_owned()));
+        assert_eq!(a.name, Some(2));
+        let _: Option<CommonName> = a.name;
+        let b: BCommon = o.b.unwrap();
+        assert_eq!(b.id, Some(3));
+        let _: Option<Name> = o.name;
+    }
+}
+
+schemafy::schemafy!(
+    root: JsonPointer
+    "tests/json-pointer.json"
+);
+
+#[test]
+fn json_pointer_refs() {
+    let mut o: JsonPointer = serde_json::from_str(
+        r#"{
+            "work": {"street": "a"},
+            "holiday": {"street": "b"},
+            "origin": {"x": 1.0},
+            "escaped": 1,
+            "encoded": true,
+            "extra": {"note": "c"},
+            "tag": "a",
+            "alias": "b"
+        }"#,
+    )
+    .unwrap();
+    // Both references to `#/properties/home` share a type with `home` itself
+    let work: Home = o.work.take().unwrap();
+    let holiday: Home = o.holiday.unwrap();
+    assert_ne!(work, holiday);
+    o.home = Some(Home {
+        street: Some("c".into()),
+    });
+    o.work = o.home.clone();
+    assert_eq!(o.origin, Some(CoordsItem0 { x: Some(1.0) }));
+    let _: Option<Vec<CoordsItem0>> = o.coords;
+    assert_eq!(o.escaped, Some(1));
+    assert_eq!(o.encoded, Some(true));
+    assert_eq!(o.extra.unwrap().note, Some("c".into()));
+    assert_eq!(o.tag, Some(Tag::A));
+    assert_eq!(o.alias, Some(Tag::B));
+}
+
+mod options {
+    schemafy::schemafy!(
+        root: Options,
+        derive: [Eq, Hash],
+        visibility: pub(crate),
+        schemafy_path: ::schemafy_core,
+        map: ::std::collections::BTreeMap,
+        strict: true,
+        "tests/options.json"
+    );
+}
+
+#[test]
+fn macro_options() {
+    fn is_hashable<T: Eq + std::hash::Hash>(_: &T) {}
+
+    let options: options::Options =
+        serde_json::from_str(r#"{ "name": "a", "tags": { "b": "c" }, "size": "small" }"#).unwrap();
+    is_hashable(&options);
+    assert_eq!(options.size, Some(options::Size::Small));
+    assert_eq!(options.tags.unwrap()["b"], "c");
+    // `strict` rejects the fields the schema does not declare
+    serde_json::from_str::<options::Options>(r#"{ "name": "a", "color": "red" }"#).unwrap_err();
+}
+
+#[cfg(all(
+    feature = "chrono",
+    feature = "email",
+    feature = "url",
+    feature = "uuid"
+))]
+#[test]
+fn formats() {
+    schemafy::schemafy!(
+        root: Formats,
+        format_crates: [chrono, email, net, url, uuid],
+        "tests/formats.json"
+    );
+
+    let json = r#"{"at":"12:00:00+00:00","color":"red","created":"2020-01-02T03:04:05+01:00","day":"2020-01-02","email":"a@example.com","homepage":"https://example.com/","id":"67e55044-10b1-426f-9247-bb680e5fe0c8","ipv4":"127.0.0.1","ipv6":"::1"}"#;
+    let formats: Formats = serde_json::from_str(json).unwrap();
+    assert_eq!(formats.day.to_string(), "2020-01-02");
+    assert!(formats.ipv_6.is_loopback());
+    assert_eq!(serde_json::to_string(&formats).unwrap(), json);
+    serde_json::from_str::<Formats>(&json.replace("2020-01-02", "today")).unwrap_err();
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_formats() {
+    schemafy::schemafy!(
+        root: Formats,
+        format_crates: [time],
+        "tests/formats.json"
+    );
+
+    let json = r#"{"at":"12:00:00Z","color":"red","created":"2020-01-02T03:04:05+01:00","day":"2020-01-02","email":"a@example.com","homepage":"https://example.com/","id":"67e55044-10b1-426f-9247-bb680e5fe0c8","ipv4":"127.0.0.1","ipv6":"::1"}"#;
+    let formats: Formats = serde_json::from_str(json).unwrap();
+    assert_eq!(formats.created.hour(), 3);
+    assert_eq!(formats.at.time.hour(), 12);
+    assert_eq!(formats.day.year(), 2020);
+    // Only the formats of `time` are mapped
+    let _: &String = &formats.id;
+    assert_eq!(serde_json::to_string(&formats).unwrap(), json);
+}
+
+schemafy::schemafy!(
+    root: Integers,
+    narrow_integers: true,
+    "tests/integers.json"
+);
+
+mod wide_integers {
+    schemafy::schemafy!(
+        root: WideIntegers
+        "tests/integers.json"
+    );
+}
+
+#[test]
+fn integers() {
+    let json = r#"{"byte":255,"count":-1,"id":18446744073709551615,"offset":-2}"#;
+    let integers: Integers = serde_json::from_str(json).unwrap();
+    let _: (u8, i32, u64, i64) = (integers.byte, integers.count, integers.id, integers.offset);
+    assert_eq!(serde_json::to_string(&integers).unwrap(), json);
+    serde_json::from_str::<Integers>(&json.replace("255", "256")).unwrap_err();
+
+    let wide: wide_integers::WideIntegers =
+        serde_json::from_str(&json.replace("18446744073709551615", "1")).unwrap();
+    let _: (i64, i64, i64, i64) = (wide.byte, wide.count, wide.id, wide.offset);
+}
+
+schemafy::schemafy!(
+    root: Issue
+    "tests/inline-enums.json"
+);
+
+#[test]
+fn inline_enums() {
+    let json = r#"{"owner":{"role":"admin"},"status":"in-progress","tags":["red","green"]}"#;
+    let issue: Issue = serde_json::from_str(json).unwrap();
+    assert_eq!(issue.status, IssueStatus::InProgress);
+    assert_eq!(
+        issue.tags,
+        Some(vec![IssueItemTags::Red, IssueItemTags::Green])
+    );
+    assert_eq!(
+        issue.owner.as_ref().unwrap().role,
+        Some(IssueOwnerRole::Admin)
+    );
+    assert_eq!(serde_json::to_string(&issue).unwrap(), json);
+    serde_json::from_str::<Issue>(&json.replace("in-progress", "todo")).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: Enums
+    "tests/enums.json"
+);
+
+#[test]
+fn enums() {
+    let json = r#"{"level":2,"mode":false,"offset":-1,"setting":2.5}"#;
+    let enums: Enums = serde_json::from_str(json).unwrap();
+    assert_eq!(enums.level, Level::V2);
+    assert_eq!(enums.offset, Offset::VMinus1);
+    assert_eq!(enums.setting, Some(Setting_::V2Point5));
+    assert_eq!(enums.mode, EnumsMode::False);
+    assert_eq!(serde_json::to_string(&enums).unwrap(), json);
+
+    for setting in ["\"auto\"", "1", "2.5", "true", "null"] {
+        let json = json.replace("2.5", setting);
+        let enums: Enums = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&enums).unwrap(), json);
+    }
+    // Numbers are compared by value
+    let enums: Enums = serde_json::from_str(&json.replace("2.5", "1.0")).unwrap();
+    assert_eq!(enums.setting, Some(Setting_::V1));
+    for setting in ["\"1\"", "2", "false"] {
+        serde_json::from_str::<Enums>(&json.replace("2.5", setting)).unwrap_err();
+    }
+    serde_json::from_str::<Enums>(&json.replace(r#""level":2"#, r#""level":4"#)).unwrap_err();
+
+    // Clashing variant names get a number, and typed enums defined inline are enums too
+    let json = r#"{"case":"A","enabled":true,"level":2,"mode":false,"offset":-1,"priority":3,"scale":1,"setting":2.5}"#;
+    let enums: Enums = serde_json::from_str(json).unwrap();
+    assert_eq!(enums.case, Some(EnumsCase::A2));
+    assert_eq!(enums.enabled, Some(EnumsEnabled::True));
+    assert_eq!(enums.priority, Some(EnumsPriority::V3));
+    assert_eq!(enums.scale, Some(EnumsScale::V1));
+    assert_eq!(serde_json::to_string(&enums).unwrap(), json);
+    for case in ["\"a\"", "1", "\"1\""] {
+        let json = json.replace("\"A\"", case);
+        let enums: Enums = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&enums).unwrap(), json);
+    }
+    // The variants of `"a"`, `"A"`, `1` and `"1"`
+    let _ = [EnumsCase::A, EnumsCase::A2, EnumsCase::V1, EnumsCase::_1];
+    serde_json::from_str::<Enums>(&json.replace(r#""priority":3"#, r#""priority":4"#)).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: Numbers
+    "tests/numbers.json"
+);
+
+mod exact_numbers {
+    schemafy::schemafy!(
+        root: ExactNumbers,
+        number_type: Number,
+        "tests/numbers.json"
+    );
+}
+
+#[test]
+fn numbers() {
+    let json = r#"{"price":19.99,"ratio":0.5}"#;
+    let numbers: Numbers = serde_json::from_str(json).unwrap();
+    let _: f64 = numbers.ratio;
+    assert_eq!(serde_json::to_string(&numbers).unwrap(), json);
+
+    // 2^53 + 1, which an `f64` rounds
+    let json = r#"{"price":19.99,"ratio":9007199254740993}"#;
+    let numbers: Numbers = serde_json::from_str(json).unwrap();
+    assert_ne!(serde_json::to_string(&numbers).unwrap(), json);
+
+    let exact: exact_numbers::ExactNumbers = serde_json::from_str(json).unwrap();
+    let _: serde_json::Number = exact.ratio;
+    assert_eq!(exact.ratio.as_u64(), Some(9_007_199_254_740_993));
+    assert_eq!(serde_json::to_string(&exact).unwrap(), json);
+}
+
+#[cfg(feature = "decimal")]
+mod decimal_numbers {
+    schemafy::schemafy!(
+        root: DecimalNumbers,
+        number_type: Decimal,
+        "tests/numbers.json"
+    );
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn decimal_numbers() {
+    let json = r#"{"price":12345678901234567.89,"ratio":0.5}"#;
+    let numbers: decimal_numbers::DecimalNumbers = serde_json::from_str(json).unwrap();
+    assert_eq!(numbers.price.to_string(), "12345678901234567.89");
+    assert_eq!(numbers.ratio.to_string(), "0.5");
+    assert_eq!(serde_json::to_string(&numbers).unwrap(), json);
+}
+
+mod private_fields {
+    schemafy::schemafy!(
+        root: Private,
+        field_visibility: pub(self),
+        "tests/options.json"
+    );
+
+    impl Private {
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+    }
+}
+
+#[test]
+fn private_fields() {
+    let private: private_fields::Private = serde_json::from_str(r#"{ "name": "a" }"#).unwrap();
+    assert_eq!(private.name(), "a");
+}
+
+schemafy::schemafy!(
+    root: InlinePoint,
+    json = r#"{
+        "type": "object",
+        "properties": {
+            "x": { "type": "integer" },
+            "y": { "type": "integer" }
+        },
+        "required": ["x", "y"]
+    }"#
+);
+
+#[test]
+fn inline_json() {
+    let point: InlinePoint = serde_json::from_str(r#"{ "x": 1, "y": 2 }"#).unwrap();
+    assert_eq!((point.x, point.y), (1, 2));
+}